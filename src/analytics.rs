@@ -0,0 +1,382 @@
+/*!
+ * Where did the turns go? Metrics over recorded games, one report per game
+ * and a summary over many.
+ *
+ * A turn is lost when we stood at a module and either waited or sent a
+ * command that had no effect. Lost turns are grouped by the first word of the
+ * message sent with the command, which for the goal machine names its goal.
+ */
+
+use super::carried_by;
+use super::command;
+use super::connect_options;
+use super::history;
+use super::replay;
+use super::sample;
+use super::turn_diff;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SampleCounts {
+   drawn: u32,
+   taken_from_cloud: u32,
+   diagnosed: u32,
+   dropped: u32,
+   produced: u32,
+}
+
+impl SampleCounts {
+   pub fn get_drawn(&self) -> u32 {
+      self.drawn
+   }
+   pub fn get_taken_from_cloud(&self) -> u32 {
+      self.taken_from_cloud
+   }
+   pub fn get_diagnosed(&self) -> u32 {
+      self.diagnosed
+   }
+   pub fn get_dropped(&self) -> u32 {
+      self.dropped
+   }
+   pub fn get_produced(&self) -> u32 {
+      self.produced
+   }
+
+   fn add(&mut self, other: &SampleCounts) {
+      self.drawn += other.drawn;
+      self.taken_from_cloud += other.taken_from_cloud;
+      self.diagnosed += other.diagnosed;
+      self.dropped += other.dropped;
+      self.produced += other.produced;
+   }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GameReport {
+   travelling: u16,
+   connecting: u16,
+   waiting: u16,
+   // indexed by rank - 1
+   samples: [SampleCounts; 3],
+   molecules_picked: i16,
+   molecules_used: i16,
+   projects: [u16; 2],
+   // our score and the enemy's, turn by turn
+   scores: Vec<(i16, i16)>,
+   lost_by_goal: BTreeMap<String, u16>,
+}
+
+const NO_GOAL: &str = "-";
+const TIMELINE_STEP: usize = 25;
+
+fn goal_of(output: Option<&command::Output>) -> String {
+   output
+      .and_then(|output| output.get_message())
+      .and_then(|message| message.split_whitespace().next())
+      .unwrap_or(NO_GOAL)
+      .to_owned()
+}
+
+impl GameReport {
+   pub fn from_replay(game: &replay::Replay) -> Self {
+      let mut report = GameReport::default();
+      let states = game.get_states();
+      for state in states {
+         report.scores.push((state.get_my_robot().get_score(), state.get_enemy_robot().get_score()));
+      }
+      for (turn, pair) in states.windows(2).enumerate() {
+         report.count_turn(game, turn, &pair[0], &pair[1]);
+      }
+      if let Some(last) = states.last() {
+         for (index, robot) in [last.get_my_robot(), last.get_enemy_robot()].iter().enumerate() {
+            report.projects[index] = game
+               .get_projects()
+               .iter()
+               .filter(|project| (*project - robot.get_expertise()).is_not_positive())
+               .count() as u16;
+         }
+      }
+      report
+   }
+
+   fn count_turn(&mut self, game: &replay::Replay, turn: usize, previous: &history::TurnState, current: &history::TurnState) {
+      let before = previous.get_my_robot();
+      let recorded = game.get_my_command(turn);
+      let diff = turn_diff::TurnDiff::new(previous, current, recorded.map(command::Output::get_action));
+      let inferred = diff.get_my_command();
+      let sent = recorded.map(command::Output::get_action).unwrap_or(inferred);
+      let travelling = before.get_eta() > 0
+         || matches!(sent, command::Command::Goto(target) if target != before.get_location() && !diff.my_command_failed());
+      if travelling {
+         self.travelling += 1;
+      } else if matches!(sent, command::Command::Connect(_)) && !diff.my_command_failed() {
+         self.connecting += 1;
+         if let command::Command::Connect(connect_options::ConnectOptions::MoleculeType(_)) = inferred {
+            self.molecules_picked += 1;
+         }
+      } else {
+         self.waiting += 1;
+         *self.lost_by_goal.entry(goal_of(recorded)).or_default() += 1;
+      }
+
+      for (id, event) in history::History::sample_events_between(previous, current) {
+         let found = current.find_sample(id).or_else(|| previous.find_sample(id));
+         let rank = match found {
+            Some((_, sample)) => sample.get_rank().as_value() as usize - 1,
+            None => continue,
+         };
+         let counts = &mut self.samples[rank];
+         match event {
+            history::SampleEvent::Drawn(carried_by::CarriedBy::Me) => counts.drawn += 1,
+            history::SampleEvent::TakenFromCloud(carried_by::CarriedBy::Me) => counts.taken_from_cloud += 1,
+            history::SampleEvent::Diagnosed(carried_by::CarriedBy::Me) => counts.diagnosed += 1,
+            history::SampleEvent::Dropped(carried_by::CarriedBy::Me) => counts.dropped += 1,
+            history::SampleEvent::Produced(carried_by::CarriedBy::Me) => {
+               counts.produced += 1;
+               if let Some((_, produced)) = previous.find_sample(id) {
+                  self.molecules_used += (produced.get_cost() - before.get_expertise()).set_minues_to_zero().len();
+               }
+            }
+            _ => {}
+         }
+      }
+   }
+
+   pub fn get_travelling(&self) -> u16 {
+      self.travelling
+   }
+   pub fn get_connecting(&self) -> u16 {
+      self.connecting
+   }
+   pub fn get_waiting(&self) -> u16 {
+      self.waiting
+   }
+   pub fn get_samples(&self, rank: sample::SampleRank) -> &SampleCounts {
+      &self.samples[rank.as_value() as usize - 1]
+   }
+   // picked up but never spent on a sample
+   pub fn get_molecules_wasted(&self) -> i16 {
+      self.molecules_picked - self.molecules_used
+   }
+   pub fn get_projects(&self) -> [u16; 2] {
+      self.projects
+   }
+   pub fn get_scores(&self) -> &Vec<(i16, i16)> {
+      &self.scores
+   }
+   pub fn get_lost_by_goal(&self) -> &BTreeMap<String, u16> {
+      &self.lost_by_goal
+   }
+
+   pub fn final_score(&self) -> (i16, i16) {
+      self.scores.last().copied().unwrap_or((0, 0))
+   }
+}
+
+fn write_lost_turns<T: fmt::Display>(f: &mut fmt::Formatter, lost_by_goal: &BTreeMap<String, T>) -> fmt::Result {
+   let parts = lost_by_goal.iter().map(|(goal, turns)| format!("{} {}", goal, turns)).collect::<Vec<_>>();
+   writeln!(f, "lost turns by goal: {}", if parts.is_empty() { "-".to_owned() } else { parts.join(", ") })
+}
+
+fn write_samples(f: &mut fmt::Formatter, samples: &[SampleCounts; 3]) -> fmt::Result {
+   writeln!(f, "samples   drawn  cloud  diagnosed  dropped  produced")?;
+   for (index, counts) in samples.iter().enumerate() {
+      writeln!(
+         f,
+         "  rank {}  {:>5}  {:>5}  {:>9}  {:>7}  {:>8}",
+         index + 1,
+         counts.drawn,
+         counts.taken_from_cloud,
+         counts.diagnosed,
+         counts.dropped,
+         counts.produced
+      )?;
+   }
+   Ok(())
+}
+
+impl fmt::Display for GameReport {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let (mine, theirs) = self.final_score();
+      writeln!(f, "score {} - {}", mine, theirs)?;
+      writeln!(f, "turns: travelling {}, connecting {}, waiting {}", self.travelling, self.connecting, self.waiting)?;
+      write_samples(f, &self.samples)?;
+      writeln!(f, "molecules picked {}, never used {}", self.molecules_picked, self.get_molecules_wasted())?;
+      writeln!(f, "projects completed {} - {}", self.projects[0], self.projects[1])?;
+      let timeline = self
+         .scores
+         .iter()
+         .enumerate()
+         .filter(|(turn, _)| turn % TIMELINE_STEP == 0 || *turn + 1 == self.scores.len())
+         .map(|(turn, (mine, theirs))| format!("t{} {}:{}", turn, mine, theirs))
+         .collect::<Vec<_>>();
+      writeln!(f, "timeline {}", timeline.join(" | "))?;
+      write_lost_turns(f, &self.lost_by_goal)
+   }
+}
+
+/**
+ * Totals over many games, displayed as averages per game. Kept in wide
+ * types: a season of recorded games easily outgrows a single game's counts.
+ */
+#[derive(Debug, Default)]
+pub struct Summary {
+   games: u32,
+   wins: u32,
+   draws: u32,
+   travelling: u32,
+   connecting: u32,
+   waiting: u32,
+   samples: [SampleCounts; 3],
+   molecules_picked: i64,
+   molecules_used: i64,
+   projects: [u32; 2],
+   // sums of the final scores, ours and the enemy's
+   scores: (i64, i64),
+   lost_by_goal: BTreeMap<String, u32>,
+}
+
+impl Summary {
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   pub fn add(&mut self, report: &GameReport) {
+      let (mine, theirs) = report.final_score();
+      self.games += 1;
+      if mine > theirs {
+         self.wins += 1;
+      } else if mine == theirs {
+         self.draws += 1;
+      }
+      self.travelling += report.travelling as u32;
+      self.connecting += report.connecting as u32;
+      self.waiting += report.waiting as u32;
+      for (total, counts) in self.samples.iter_mut().zip(report.samples.iter()) {
+         total.add(counts);
+      }
+      self.molecules_picked += report.molecules_picked as i64;
+      self.molecules_used += report.molecules_used as i64;
+      self.projects[0] += report.projects[0] as u32;
+      self.projects[1] += report.projects[1] as u32;
+      for (goal, turns) in report.lost_by_goal.iter() {
+         *self.lost_by_goal.entry(goal.clone()).or_default() += *turns as u32;
+      }
+      self.scores.0 += mine as i64;
+      self.scores.1 += theirs as i64;
+   }
+
+   pub fn get_games(&self) -> u32 {
+      self.games
+   }
+   pub fn get_wins(&self) -> u32 {
+      self.wins
+   }
+   pub fn get_waiting(&self) -> u32 {
+      self.waiting
+   }
+   pub fn get_scores(&self) -> (i64, i64) {
+      self.scores
+   }
+}
+
+impl fmt::Display for Summary {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let games = self.games.max(1) as f64;
+      let average = |total: i64| total as f64 / games;
+      let (mine, theirs) = self.scores;
+      writeln!(
+         f,
+         "{} games: {} won, {} drawn, {} lost",
+         self.games,
+         self.wins,
+         self.draws,
+         self.games - self.wins - self.draws
+      )?;
+      writeln!(f, "average score {:.1} - {:.1}", average(mine), average(theirs))?;
+      writeln!(
+         f,
+         "average turns: travelling {:.1}, connecting {:.1}, waiting {:.1}",
+         average(self.travelling as i64),
+         average(self.connecting as i64),
+         average(self.waiting as i64)
+      )?;
+      write_samples(f, &self.samples)?;
+      writeln!(
+         f,
+         "average molecules picked {:.1}, never used {:.1}",
+         average(self.molecules_picked),
+         average(self.molecules_picked - self.molecules_used)
+      )?;
+      writeln!(f, "projects completed {} - {}", self.projects[0], self.projects[1])?;
+      write_lost_turns(f, &self.lost_by_goal)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::replay::Replay;
+   use super::super::sample::SampleRank;
+   use super::{GameReport, Summary};
+   use std::io::Cursor;
+
+   // draw a sample, walk to DIAGNOSIS, diagnose it, take a molecule and wait once
+   const RECORDING: &str = "1\n0 1 0 0 0\n\
+      SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n0\n\
+      SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n1\n\
+      0 0 1 B -1 -1 -1 -1 -1 -1\n\
+      DIAGNOSIS 2 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n1\n\
+      0 0 1 B -1 -1 -1 -1 -1 -1\n\
+      DIAGNOSIS 1 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n1\n\
+      0 0 1 B -1 -1 -1 -1 -1 -1\n\
+      DIAGNOSIS 0 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n1\n\
+      0 0 1 B -1 -1 -1 -1 -1 -1\n\
+      DIAGNOSIS 0 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n1\n\
+      0 0 1 B 1 2 0 0 0 0\n\
+      DIAGNOSIS 0 0 0 0 0 0 0 0 0 0 0 0\nSTART_POS 0 0 0 0 0 0 0 0 0 0 0 0\n5 5 5 5 5\n1\n\
+      0 0 1 B 1 2 0 0 0 0\n";
+
+   const COMMANDS: &str = "CONNECT 1 TAKE r1\nGOTO DIAGNOSIS DIAGNOSE s0\nWAIT DIAGNOSE s0\nWAIT DIAGNOSE s0\n\
+      CONNECT 0 DIAGNOSE s0\nCONNECT A GATHER s0 need A2\nWAIT\n";
+
+   #[test]
+   fn counts_where_the_turns_went() {
+      let mut game = Replay::read_from(&mut Cursor::new(RECORDING));
+      game.read_commands_from(&mut Cursor::new(COMMANDS));
+      let report = GameReport::from_replay(&game);
+      assert_eq!(report.get_travelling(), 3);
+      assert_eq!(report.get_connecting(), 2);
+      assert_eq!(report.get_waiting(), 1);
+      assert_eq!(report.get_lost_by_goal().get("GATHER"), Some(&1));
+      let rank_one = report.get_samples(SampleRank::LittleHealth);
+      assert_eq!((rank_one.get_drawn(), rank_one.get_diagnosed(), rank_one.get_produced()), (1, 1, 0));
+      assert_eq!(report.get_projects(), [0, 0]);
+      assert_eq!(report.get_scores().len(), 7);
+      assert!(report.to_string().contains("turns: travelling 3, connecting 2, waiting 1"));
+
+      let mut summary = Summary::new();
+      summary.add(&report);
+      summary.add(&report);
+      assert_eq!(summary.get_games(), 2);
+      assert!(summary.to_string().contains("2 games: 0 won, 2 drawn, 0 lost"));
+   }
+
+   #[test]
+   fn summary_totals_outgrow_a_single_game() {
+      let report = GameReport {
+         waiting: u16::MAX,
+         molecules_picked: i16::MAX,
+         scores: vec![(0, 0), (i16::MAX, 10)],
+         ..GameReport::default()
+      };
+      let mut summary = Summary::new();
+      for _ in 0..3 {
+         summary.add(&report);
+      }
+      assert_eq!(summary.get_wins(), 3);
+      assert_eq!(summary.get_waiting(), 3 * u16::MAX as u32);
+      assert_eq!(summary.get_scores(), (3 * i16::MAX as i64, 30));
+      assert!(summary.to_string().contains("average score 32767.0 - 10.0"));
+   }
+}
@@ -0,0 +1,60 @@
+/*!
+ * Where two versions of our bot first play differently.
+ *
+ * ```text
+ * diverge <first> <second> [--seed <n>] [--opponent <strategy>]
+ * ```
+ *
+ * Each version is a strategy name or the path of another build (anything
+ * with a `/`), which is run as a child process. Both play the same seeded
+ * game against their own copy of the opponent, `baseline` by default.
+ */
+
+use code_4_life::external;
+use code_4_life::simulation;
+use code_4_life::strategy;
+use std::env;
+use std::process;
+
+fn usage() -> ! {
+   eprintln!("usage: diverge <first> <second> [--seed <n>] [--opponent <strategy>]");
+   eprintln!("strategies: {}", strategy::names().join(", "));
+   process::exit(1);
+}
+
+fn load(version: &str) -> Box<dyn strategy::Strategy> {
+   if version.contains('/') {
+      match external::ExternalStrategy::spawn(version, &[]) {
+         Ok(bot) => Box::new(bot),
+         Err(error) => {
+            eprintln!("cannot start {}: {}", version, error);
+            process::exit(1);
+         }
+      }
+   } else {
+      strategy::by_name(version).unwrap_or_else(|| usage())
+   }
+}
+
+fn main() {
+   let arguments = env::args().skip(1).collect::<Vec<_>>();
+   let mut versions = Vec::new();
+   let mut seed = 0;
+   let mut opponent = "baseline".to_owned();
+   let mut words = arguments.iter();
+   while let Some(word) = words.next() {
+      match word.as_str() {
+         "--seed" => seed = words.next().and_then(|value| value.parse().ok()).unwrap_or_else(|| usage()),
+         "--opponent" => opponent = words.next().cloned().unwrap_or_else(|| usage()),
+         _ => versions.push(word.as_str()),
+      }
+   }
+   if versions.len() != 2 || strategy::by_name(&opponent).is_none() {
+      usage();
+   }
+
+   let report = simulation::first_divergence(seed, load(versions[0]), load(versions[1]), &|| {
+      strategy::by_name(&opponent).unwrap()
+   });
+   print!("{}", report);
+}
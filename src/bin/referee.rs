@@ -0,0 +1,114 @@
+/*!
+ * Code4Life referee for community local testers such as brutaltester.
+ *
+ * The tester starts the referee and relays between it and the players:
+ *
+ * ```text
+ * ###Seed 42          (optional, before ###Start)
+ * ###Start 2          tester -> referee
+ * ###Input 0          referee -> tester, followed by the lines for player 0
+ * ###Output 0 1       referee -> tester, the tester answers with 1 line of player 0
+ * ###End 0 1          referee -> tester, player indices from first to last,
+ *                     tied players written together ("###End 01")
+ * ```
+ *
+ * `referee --record <prefix>` also saves what player 0 read and printed to
+ * `<prefix>.in` and `<prefix>.out`, for the replay viewer.
+ */
+
+use code_4_life::command;
+use code_4_life::game;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+fn read_line(input: &mut impl BufRead) -> Option<String> {
+   let mut line = String::new();
+   match input.read_line(&mut line) {
+      Ok(0) | Err(_) => None,
+      Ok(_) => Some(line.trim().to_owned()),
+   }
+}
+
+fn main() {
+   let stdin = io::stdin();
+   let mut input = stdin.lock();
+   let stdout = io::stdout();
+   let mut output = stdout.lock();
+
+   let mut seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64);
+   loop {
+      let line = match read_line(&mut input) {
+         Some(line) => line,
+         None => return,
+      };
+      let mut words = line.split_whitespace();
+      match words.next() {
+         Some("###Seed") => {
+            if let Some(value) = words.next().and_then(|value| value.parse().ok()) {
+               seed = value;
+            }
+         }
+         Some("###Start") => {
+            if words.next() != Some("2") {
+               eprintln!("Code4Life is played by exactly 2 players");
+               return;
+            }
+            break;
+         }
+         _ => eprintln!("ignoring {}", line),
+      }
+   }
+
+   let mut recording = match (env::args().nth(1).as_deref(), env::args().nth(2)) {
+      (Some("--record"), Some(prefix)) => {
+         let create = |extension: &str| fs::File::create(format!("{}.{}", prefix, extension)).unwrap();
+         Some((create("in"), create("out")))
+      }
+      _ => None,
+   };
+
+   let mut game = game::Game::new(seed);
+   eprintln!("seed {}", seed);
+   while !game.is_over() {
+      let mut commands = [None, None];
+      for (player, command) in commands.iter_mut().enumerate() {
+         let mut text = String::new();
+         if game.get_turn() == 0 {
+            text.push_str(&game.initial_input());
+         }
+         text.push_str(&game.turn_input(player));
+         write!(output, "###Input {}\n{}###Output {} 1\n", player, text, player).unwrap();
+         output.flush().unwrap();
+
+         let location = *game.get_robot(player).get_location();
+         let line = read_line(&mut input);
+         if let (Some((recorded_input, recorded_output)), 0) = (recording.as_mut(), player) {
+            recorded_input.write_all(text.as_bytes()).unwrap();
+            writeln!(recorded_output, "{}", line.as_deref().unwrap_or("")).unwrap();
+         }
+         *command = line.and_then(|line| match command::Output::parse_at(&line, &location) {
+            Ok(output) => Some(output.into_action()),
+            Err(reason) => {
+               eprintln!("player {} sent {:?}: {}", player, line, reason);
+               None
+            }
+         });
+      }
+      game.play(commands);
+   }
+
+   let ranking = game
+      .ranking()
+      .iter()
+      .map(|group| group.iter().map(|player| player.to_string()).collect::<String>())
+      .collect::<Vec<_>>();
+   let scores = game.get_scores();
+   eprintln!("turn {}: scores {} {}", game.get_turn(), scores[0], scores[1]);
+   writeln!(output, "###End {}", ranking.join(" ")).unwrap();
+   output.flush().unwrap();
+}
@@ -0,0 +1,70 @@
+/*!
+ * Replay viewer for recorded games.
+ *
+ * ```text
+ * replay <input> [<output>]              every turn in the terminal, for `less -R`
+ * replay <input> [<output>] --animate    one turn at a time in place
+ * replay <input> [<output>] --html page  a single HTML page
+ * ```
+ *
+ * `<input>` is what the bot read (e.g. saved with `tee`, or written by
+ * `referee --record`), `<output>` what it printed.
+ */
+
+use code_4_life::replay;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const FRAME_DELAY: Duration = Duration::from_millis(300);
+
+fn open(path: &str) -> io::BufReader<fs::File> {
+   match fs::File::open(path) {
+      Ok(file) => io::BufReader::new(file),
+      Err(error) => {
+         eprintln!("cannot open {}: {}", path, error);
+         process::exit(1);
+      }
+   }
+}
+
+fn main() {
+   let mut paths = Vec::new();
+   let mut html = None;
+   let mut animate = false;
+   let mut args = env::args().skip(1);
+   while let Some(arg) = args.next() {
+      match arg.as_str() {
+         "--html" => html = args.next(),
+         "--animate" => animate = true,
+         _ => paths.push(arg),
+      }
+   }
+   if paths.is_empty() || paths.len() > 2 {
+      eprintln!("usage: replay <input> [<output>] [--animate | --html <page>]");
+      process::exit(1);
+   }
+
+   let mut game = replay::Replay::read_from(&mut open(&paths[0]));
+   if let Some(output) = paths.get(1) {
+      game.read_commands_from(&mut open(output));
+   }
+   if let Some(page) = html {
+      if let Err(error) = fs::write(&page, game.render_html()) {
+         eprintln!("cannot write {}: {}", page, error);
+         process::exit(1);
+      }
+      return;
+   }
+   for turn in 0..game.len() {
+      if animate {
+         print!("{}", game.render_ansi(turn));
+         thread::sleep(FRAME_DELAY);
+      } else {
+         println!("{}", game.describe_turn(turn));
+      }
+   }
+}
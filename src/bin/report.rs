@@ -0,0 +1,56 @@
+/*!
+ * Metrics over a directory of recorded games.
+ *
+ * ```text
+ * report <directory>
+ * ```
+ *
+ * Every `<name>.in` is a game as our bot read it, `<name>.out` (optional)
+ * what it printed; `referee --record` writes both.
+ */
+
+use code_4_life::analytics;
+use code_4_life::replay;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+fn main() {
+   let directory = match env::args().nth(1) {
+      Some(directory) => directory,
+      None => {
+         eprintln!("usage: report <directory>");
+         process::exit(1);
+      }
+   };
+   let mut inputs = match fs::read_dir(&directory) {
+      Ok(entries) => entries
+         .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+         .filter(|path| path.extension().is_some_and(|extension| extension == "in"))
+         .collect::<Vec<_>>(),
+      Err(error) => {
+         eprintln!("cannot read {}: {}", directory, error);
+         process::exit(1);
+      }
+   };
+   inputs.sort();
+
+   let mut summary = analytics::Summary::new();
+   for input in inputs {
+      let mut game = match fs::File::open(&input) {
+         Ok(file) => replay::Replay::read_from(&mut io::BufReader::new(file)),
+         Err(error) => {
+            eprintln!("skipping {}: {}", input.display(), error);
+            continue;
+         }
+      };
+      if let Ok(file) = fs::File::open(input.with_extension("out")) {
+         game.read_commands_from(&mut io::BufReader::new(file));
+      }
+      let report = analytics::GameReport::from_replay(&game);
+      println!("== {} ==\n{}", input.display(), report);
+      summary.add(&report);
+   }
+   println!("== all games ==\n{}", summary);
+}
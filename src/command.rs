@@ -1,7 +1,12 @@
 use super::connect_options;
 use super::module;
+use super::molecules;
+use super::robot;
+use super::sample;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Command {
    Goto(module::Module),
    Connect(connect_options::ConnectOptions),
@@ -9,11 +14,424 @@ pub enum Command {
 }
 
 impl Command {
-   pub fn to_string(&self) -> String {
+   const MAX_SAMPLES: usize = 3;
+   const MAX_MOLECULES: i16 = 10;
+
+   // the command with a speech bubble, see Output::with_message
+   pub fn with_message(self, message: &str) -> Output {
+      Output::new(self).with_message(message)
+   }
+
+   /**
+    * Checks the command against the module rules for `robot`, given the
+    * molecules in stock and the samples lying in the cloud.
+    */
+   pub fn check(
+      &self,
+      robot: &robot::Robot,
+      available: &molecules::Molecules,
+      cloud: &[sample::Sample],
+   ) -> Result<(), &'static str> {
+      if robot.get_eta() > 0 {
+         // the referee ignores commands while travelling
+         return Ok(());
+      }
+      let options = match self {
+         Command::Wait => return Ok(()),
+         Command::Goto(module::Module::Spawn) => return Err("cannot go back to the start position"),
+         Command::Goto(target) if target == robot.get_location() => return Err("already at that module"),
+         Command::Goto(_) => return Ok(()),
+         Command::Connect(options) => options,
+      };
+      let held_sample = |id: u8| robot.get_held_samples().iter().find(|sample| sample.get_id() == id);
+      match (robot.get_location(), options) {
+         (module::Module::Sample, connect_options::ConnectOptions::SampleRank(_)) => {
+            if robot.get_held_samples().len() >= Self::MAX_SAMPLES {
+               return Err("already holding 3 samples");
+            }
+            Ok(())
+         }
+         (module::Module::Diagnosis, connect_options::ConnectOptions::SampleId(id)) => {
+            if held_sample(*id).is_some() {
+               return Ok(());
+            }
+            if !cloud.iter().any(|sample| sample.get_id() == *id) {
+               return Err("sample is neither held nor in the cloud");
+            }
+            if robot.get_held_samples().len() >= Self::MAX_SAMPLES {
+               return Err("no room to take a sample from the cloud");
+            }
+            Ok(())
+         }
+         (module::Module::Molecule, connect_options::ConnectOptions::MoleculeType(molecule)) => {
+            if robot.get_inventory().len() >= Self::MAX_MOLECULES {
+               return Err("already holding 10 molecules");
+            }
+            if available[*molecule] <= 0 {
+               return Err("molecule type is out of stock");
+            }
+            Ok(())
+         }
+         (module::Module::Laboratory, connect_options::ConnectOptions::SampleId(id)) => {
+            let sample = match held_sample(*id) {
+               Some(sample) => sample,
+               None => return Err("sample is not held"),
+            };
+            if let sample::SampleHealth::Unresearched = sample.get_health() {
+               return Err("sample is not diagnosed");
+            }
+            let missing = sample.get_cost() - &(robot.get_inventory() + robot.get_expertise());
+            if !missing.is_not_positive() {
+               return Err("not enough molecules for the sample");
+            }
+            Ok(())
+         }
+         (module::Module::Spawn, _) => Err("nothing to connect to at the start position"),
+         _ => Err("connect option does not match the module"),
+      }
+   }
+
+   pub fn is_legal(
+      &self,
+      robot: &robot::Robot,
+      available: &molecules::Molecules,
+      cloud: &[sample::Sample],
+   ) -> bool {
+      self.check(robot, available, cloud).is_ok()
+   }
+
+   /**
+    * Every command the referee would accept from `robot` this turn. Going to
+    * the current module is left out since it does the same as WAIT.
+    */
+   pub fn legal_commands(
+      robot: &robot::Robot,
+      available: &molecules::Molecules,
+      cloud: &[sample::Sample],
+   ) -> Vec<Command> {
+      let mut candidates = vec![Command::Wait];
+      if robot.get_eta() > 0 {
+         return candidates;
+      }
+      for module in module::Module::ALL {
+         if &module != robot.get_location() {
+            candidates.push(Command::Goto(module));
+         }
+      }
+      for rank in sample::SampleRank::ALL {
+         candidates.push(Command::Connect(connect_options::ConnectOptions::SampleRank(rank)));
+      }
+      for sample in robot.get_held_samples().iter().chain(cloud.iter()) {
+         candidates.push(Command::Connect(connect_options::ConnectOptions::SampleId(sample.get_id())));
+      }
+      for molecule in molecules::Molecule::ALL {
+         candidates.push(Command::Connect(connect_options::ConnectOptions::MoleculeType(molecule)));
+      }
+      candidates.retain(|command| command.is_legal(robot, available, cloud));
+      candidates
+   }
+
+   // protocol text as the robot at `location` would mean it, see ConnectOptions::parse_at
+   pub fn parse_at(s: &str, location: &module::Module) -> Result<Self, &'static str> {
+      match s.parse::<Command>()? {
+         Command::Connect(connect_options::ConnectOptions::SampleId(_)) => {
+            let option = s.split_whitespace().nth(1).ok_or("Missing connect option")?;
+            Ok(Command::Connect(connect_options::ConnectOptions::parse_at(option, location)?))
+         }
+         action => Ok(action),
+      }
+   }
+}
+
+// "GOTO <module>", "CONNECT <id|rank|molecule>" or "WAIT"
+impl FromStr for Command {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let mut words = s.split_whitespace();
+      let action = match words.next() {
+         Some("GOTO") => Command::Goto(words.next().ok_or("Missing module")?.parse()?),
+         Some("CONNECT") => Command::Connect(words.next().ok_or("Missing connect option")?.parse()?),
+         Some("WAIT") => Command::Wait,
+         _ => return Err("Invalid command"),
+      };
+      if words.next().is_some() {
+         return Err("Unexpected text after the command");
+      }
+      Ok(action)
+   }
+}
+
+impl fmt::Display for Command {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
       match self {
-         Command::Goto(module) => format!("GOTO {}", module.as_str()),
-         Command::Connect(options) => format!("CONNECT {}", options.to_string()),
-         _ => "WAIT".to_owned(),
+         Command::Goto(module) => write!(f, "GOTO {}", module.as_str()),
+         Command::Connect(options) => write!(f, "CONNECT {}", options),
+         Command::Wait => write!(f, "WAIT"),
+      }
+   }
+}
+
+/**
+ * What a bot prints for a turn: the command and the text the arena shows
+ * next to the robot.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Output {
+   action: Command,
+   message: Option<String>,
+}
+
+impl Output {
+   pub const MAX_MESSAGE_LENGTH: usize = 40;
+
+   pub fn new(action: Command) -> Self {
+      Self { action, message: None }
+   }
+
+   // replaces the speech bubble, truncated to what the arena displays; an empty message removes it
+   pub fn with_message(self, message: &str) -> Output {
+      let message = message.trim().chars().take(Self::MAX_MESSAGE_LENGTH).collect::<String>();
+      Self {
+         action: self.action,
+         message: if message.is_empty() { None } else { Some(message) },
+      }
+   }
+
+   pub fn get_action(&self) -> &Command {
+      &self.action
+   }
+   pub fn into_action(self) -> Command {
+      self.action
+   }
+   pub fn get_message(&self) -> Option<&str> {
+      self.message.as_deref()
+   }
+
+   // splits the line after the words of the command, the rest is the message
+   fn split(s: &str) -> (String, String) {
+      let words = s.split_whitespace().collect::<Vec<_>>();
+      let length = match words.first() {
+         Some(&"GOTO") | Some(&"CONNECT") => 2,
+         _ => 1,
+      };
+      let length = length.min(words.len());
+      (words[..length].join(" "), words[length..].join(" "))
+   }
+
+   // a printed line as the robot at `location` would mean it, see Command::parse_at
+   pub fn parse_at(s: &str, location: &module::Module) -> Result<Self, &'static str> {
+      let (action, message) = Self::split(s);
+      Ok(Command::parse_at(&action, location)?.with_message(&message))
+   }
+}
+
+impl From<Command> for Output {
+   fn from(action: Command) -> Self {
+      Self::new(action)
+   }
+}
+
+// a command, then anything after it is the arena message
+impl FromStr for Output {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let (action, message) = Self::split(s);
+      Ok(action.parse::<Command>()?.with_message(&message))
+   }
+}
+
+impl fmt::Display for Output {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match &self.message {
+         Some(message) => write!(f, "{} {}", self.action, message),
+         None => write!(f, "{}", self.action),
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::connect_options::ConnectOptions;
+   use super::super::module::Module;
+   use super::super::molecules::Molecule;
+   use super::super::sample::SampleRank;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::{Command, Output};
+
+   const PLENTY: &str = "A5 B5 C5 D5 E5";
+
+   fn check(robot: RobotBuilder, command: Command, available: &str, cloud: Vec<SampleBuilder>) -> Result<(), &'static str> {
+      let cloud = cloud.into_iter().map(|sample| sample.build()).collect::<Vec<_>>();
+      command.check(&robot.build(), &molecules(available), &cloud)
+   }
+
+   fn full_hands(module: Module) -> RobotBuilder {
+      RobotBuilder::at(module)
+         .sample(SampleBuilder::new(1))
+         .sample(SampleBuilder::new(2))
+         .sample(SampleBuilder::new(3))
+   }
+
+   #[test]
+   fn parses_protocol_text() {
+      let table = vec![
+         ("WAIT", Command::Wait.into()),
+         ("GOTO MOLECULES", Command::Goto(Module::Molecule).into()),
+         ("GOTO LABORATORY going  home", Command::Goto(Module::Laboratory).with_message("going home")),
+         ("CONNECT 12", Command::Connect(ConnectOptions::SampleId(12)).into()),
+         ("CONNECT 2", Command::Connect(ConnectOptions::SampleId(2)).into()),
+         (
+            "CONNECT D need D2",
+            Command::Connect(ConnectOptions::MoleculeType(Molecule::D)).with_message("need D2"),
+         ),
+         ("  WAIT  ", Command::Wait.into()),
+      ];
+      for (text, expected) in table {
+         assert_eq!(text.parse::<Output>(), Ok(expected), "{}", text);
+      }
+      for text in ["", "GOTO", "GOTO KITCHEN", "CONNECT", "CONNECT F", "CONNECT -1", "wait", "JUMP 3"] {
+         assert!(text.parse::<Output>().is_err(), "{}", text);
+      }
+      // a bare command has no room for a message
+      assert_eq!("WAIT".parse::<Command>(), Ok(Command::Wait));
+      assert!("WAIT for it".parse::<Command>().is_err());
+   }
+
+   #[test]
+   fn numbers_are_ranks_at_samples_module() {
+      assert_eq!(
+         Command::parse_at("CONNECT 3", &Module::Sample),
+         Ok(Command::Connect(ConnectOptions::SampleRank(SampleRank::LotsOfHealth)))
+      );
+      assert!(Command::parse_at("CONNECT 4", &Module::Sample).is_err());
+      assert_eq!(
+         Command::parse_at("CONNECT 3", &Module::Laboratory),
+         Ok(Command::Connect(ConnectOptions::SampleId(3)))
+      );
+      assert_eq!(
+         Command::parse_at("CONNECT B", &Module::Sample),
+         Ok(Command::Connect(ConnectOptions::MoleculeType(Molecule::B)))
+      );
+   }
+
+   #[test]
+   fn display_round_trips() {
+      let commands = vec![
+         Command::Wait,
+         Command::Goto(Module::Diagnosis),
+         Command::Connect(ConnectOptions::SampleId(40)),
+         Command::Connect(ConnectOptions::MoleculeType(Molecule::A)),
+      ];
+      for command in commands {
+         assert_eq!(command.to_string().parse::<Command>(), Ok(command.clone()));
+      }
+      let rank = Command::Connect(ConnectOptions::SampleRank(SampleRank::SomeHealth));
+      assert_eq!(Command::parse_at(&rank.to_string(), &Module::Sample), Ok(rank.clone()));
+      let annotated = rank.with_message("TAKE r2");
+      assert_eq!(Output::parse_at(&annotated.to_string(), &Module::Sample), Ok(annotated));
+   }
+
+   #[test]
+   fn messages_are_trimmed_and_truncated() {
+      let output = Command::Wait.with_message(&"x".repeat(100));
+      assert_eq!(output.get_message().map(|message| message.len()), Some(Output::MAX_MESSAGE_LENGTH));
+      assert_eq!(output.get_action(), &Command::Wait);
+
+      let replaced = output.with_message("  GATHER s12  ");
+      assert_eq!(replaced.to_string(), "WAIT GATHER s12");
+      assert_eq!(replaced.with_message(" "), Output::new(Command::Wait));
+   }
+
+   #[test]
+   fn legal_commands() {
+      let rank = Command::Connect(ConnectOptions::SampleRank(SampleRank::LittleHealth));
+      let table = vec![
+         (RobotBuilder::at(Module::Spawn), Command::Wait, vec![]),
+         (RobotBuilder::at(Module::Spawn), Command::Goto(Module::Sample), vec![]),
+         (full_hands(Module::Molecule).eta(2), rank.clone(), vec![]),
+         // still on the way, so not there yet
+         (RobotBuilder::at(Module::Molecule).eta(2), Command::Goto(Module::Molecule), vec![]),
+         (RobotBuilder::at(Module::Sample), rank, vec![]),
+         (full_hands(Module::Diagnosis), Command::Connect(ConnectOptions::SampleId(2)), vec![]),
+         (
+            RobotBuilder::at(Module::Diagnosis),
+            Command::Connect(ConnectOptions::SampleId(7)),
+            vec![SampleBuilder::new(7).health(10)],
+         ),
+         (
+            RobotBuilder::at(Module::Molecule).inventory("A9"),
+            Command::Connect(ConnectOptions::MoleculeType(Molecule::B)),
+            vec![],
+         ),
+         (
+            RobotBuilder::at(Module::Laboratory)
+               .inventory("A1")
+               .expertise("B2")
+               .sample(SampleBuilder::new(4).health(10).cost("A1 B2")),
+            Command::Connect(ConnectOptions::SampleId(4)),
+            vec![],
+         ),
+      ];
+      for (robot, command, cloud) in table {
+         let description = command.to_string();
+         assert_eq!(check(robot, command, PLENTY, cloud), Ok(()), "{}", description);
+      }
+   }
+
+   #[test]
+   fn enumerates_legal_commands() {
+      let robot = RobotBuilder::at(Module::Diagnosis).sample(SampleBuilder::new(2)).build();
+      let cloud = vec![SampleBuilder::new(8).health(10).build()];
+      let listed = Command::legal_commands(&robot, &molecules(PLENTY), &cloud)
+         .iter()
+         .map(|command| command.to_string())
+         .collect::<Vec<_>>();
+      assert_eq!(
+         listed,
+         vec!["WAIT", "GOTO SAMPLES", "GOTO MOLECULES", "GOTO LABORATORY", "CONNECT 2", "CONNECT 8"]
+      );
+
+      let travelling = RobotBuilder::at(Module::Molecule).eta(3).build();
+      assert_eq!(Command::legal_commands(&travelling, &molecules(PLENTY), &[]), vec![Command::Wait]);
+   }
+
+   #[test]
+   fn illegal_commands() {
+      let table = vec![
+         (RobotBuilder::at(Module::Sample), Command::Goto(Module::Spawn), PLENTY),
+         (RobotBuilder::at(Module::Laboratory), Command::Goto(Module::Laboratory), PLENTY),
+         (full_hands(Module::Sample), Command::Connect(ConnectOptions::SampleRank(SampleRank::LittleHealth)), PLENTY),
+         (RobotBuilder::at(Module::Sample), Command::Connect(ConnectOptions::SampleId(1)), PLENTY),
+         (RobotBuilder::at(Module::Diagnosis), Command::Connect(ConnectOptions::SampleId(9)), PLENTY),
+         (full_hands(Module::Diagnosis), Command::Connect(ConnectOptions::SampleId(8)), PLENTY),
+         (
+            RobotBuilder::at(Module::Molecule).inventory("A10"),
+            Command::Connect(ConnectOptions::MoleculeType(Molecule::B)),
+            PLENTY,
+         ),
+         (
+            RobotBuilder::at(Module::Molecule),
+            Command::Connect(ConnectOptions::MoleculeType(Molecule::E)),
+            "A5 B5 C5 D5",
+         ),
+         (
+            RobotBuilder::at(Module::Laboratory).sample(SampleBuilder::new(4).health(10).cost("A1")),
+            Command::Connect(ConnectOptions::SampleId(4)),
+            PLENTY,
+         ),
+         (
+            RobotBuilder::at(Module::Laboratory).sample(SampleBuilder::new(4)),
+            Command::Connect(ConnectOptions::SampleId(4)),
+            PLENTY,
+         ),
+         (RobotBuilder::at(Module::Spawn), Command::Connect(ConnectOptions::SampleId(4)), PLENTY),
+      ];
+      for (robot, command, available) in table {
+         let description = command.to_string();
+         let cloud = vec![SampleBuilder::new(8).health(10)];
+         assert!(check(robot, command, available, cloud).is_err(), "{}", description);
       }
    }
 }
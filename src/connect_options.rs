@@ -1,19 +1,59 @@
+use super::module;
 use super::molecules;
 use super::sample;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ConnectOptions {
    SampleId(u8),
    SampleRank(sample::SampleRank),
    MoleculeType(molecules::Molecule),
 }
 
-impl ConnectOptions {
-   pub fn to_string(&self) -> String {
+impl fmt::Display for ConnectOptions {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
       match self {
-         ConnectOptions::SampleRank(rank) => rank.as_value().to_string(),
-         ConnectOptions::MoleculeType(molecule_type) => molecule_type.as_char().to_string(),
-         ConnectOptions::SampleId(id) => id.to_string(),
+         ConnectOptions::SampleRank(rank) => write!(f, "{}", rank.as_value()),
+         ConnectOptions::MoleculeType(molecule_type) => write!(f, "{}", molecule_type.as_char()),
+         ConnectOptions::SampleId(id) => write!(f, "{}", id),
+      }
+   }
+}
+
+impl ConnectOptions {
+   /**
+    * Numbers are sample ids everywhere except at the SAMPLES module, where they
+    * are ranks; plain `parse` cannot know where the robot stands and reads ids.
+    */
+   pub fn parse_at(s: &str, location: &module::Module) -> Result<Self, &'static str> {
+      let options = s.parse::<ConnectOptions>()?;
+      match (location, options) {
+         (module::Module::Sample, ConnectOptions::SampleId(value)) => {
+            match sample::SampleRank::from_integer(value as i8) {
+               Some(rank) => Ok(ConnectOptions::SampleRank(rank)),
+               None => Err("Invalid sample rank"),
+            }
+         }
+         (_, options) => Ok(options),
+      }
+   }
+}
+
+impl FromStr for ConnectOptions {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let s = s.trim();
+      let mut chars = s.chars();
+      if let (Some(letter), None) = (chars.next(), chars.next()) {
+         if let Some(molecule) = molecules::Molecule::from_char(letter) {
+            return Ok(ConnectOptions::MoleculeType(molecule));
+         }
+      }
+      match s.parse::<u8>() {
+         Ok(id) => Ok(ConnectOptions::SampleId(id)),
+         Err(_) => Err("Invalid connect option"),
       }
    }
 }
@@ -0,0 +1,157 @@
+/*!
+ * JSON Lines export of every decision, one object per turn:
+ *
+ * ```text
+ * {"turn":3,"strategy":"goal_machine","goal":"TakeSamples","command":"CONNECT 1",
+ *  "message":"TAKE r1","elapsed_us":85,"position":"3; SAMPLES 0 0 - -; ...",
+ *  "state":{"me":{...},"enemy":{...},"available":[5,5,5,5,5],"cloud":[...],"projects":[[0,3,0,3,3]]}}
+ * ```
+ *
+ * `position` is the `notation` of the state, ready to paste into a test.
+ * Live runs export when `CODE4LIFE_EXPORT` names a file, simulations attach
+ * an `Exporter` to each player's `Memory`.
+ */
+
+use super::command;
+use super::history;
+use super::json;
+use super::molecules;
+use super::notation;
+use super::robot;
+use super::sample;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+pub const EXPORT_VARIABLE: &str = "CODE4LIFE_EXPORT";
+
+pub struct Exporter {
+   output: Box<dyn io::Write>,
+}
+
+impl fmt::Debug for Exporter {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "Exporter")
+   }
+}
+
+impl Exporter {
+   pub fn new(output: Box<dyn io::Write>) -> Self {
+      Self { output }
+   }
+
+   pub fn to_file(path: &str) -> io::Result<Self> {
+      Ok(Self::new(Box::new(io::BufWriter::new(fs::File::create(path)?))))
+   }
+
+   // a failing export must never cost us the game, so errors are only logged
+   pub fn write(&mut self, record: &json::Json) {
+      if let Err(error) = writeln!(self.output, "{}", record).and_then(|_| self.output.flush()) {
+         eprintln!("export failed: {}", error);
+      }
+   }
+}
+
+fn molecules_json(molecules: &molecules::Molecules) -> json::Json {
+   json::Json::numbers(molecules.as_array())
+}
+
+fn sample_json(sample: &sample::Sample) -> json::Json {
+   let (health, cost) = match sample.get_health() {
+      sample::SampleHealth::Researched(health) => (json::Json::Number(*health as i64), molecules_json(sample.get_cost())),
+      sample::SampleHealth::Unresearched => (json::Json::Null, json::Json::Null),
+   };
+   let gain = sample.get_expertise_gain();
+   let gain = if gain.is_empty() {
+      json::Json::Null
+   } else {
+      json::Json::String(gain.max_molecule().to_string())
+   };
+   json::Json::Object(vec![
+      ("id", json::Json::Number(sample.get_id() as i64)),
+      ("rank", json::Json::Number(sample.get_rank().as_value() as i64)),
+      ("health", health),
+      ("cost", cost),
+      ("gain", gain),
+   ])
+}
+
+fn robot_json(robot: &robot::Robot) -> json::Json {
+   json::Json::Object(vec![
+      ("module", json::Json::string(robot.get_location().as_str())),
+      ("eta", json::Json::Number(robot.get_eta() as i64)),
+      ("score", json::Json::Number(robot.get_score() as i64)),
+      ("inventory", molecules_json(robot.get_inventory())),
+      ("expertise", molecules_json(robot.get_expertise())),
+      ("samples", json::Json::Array(robot.get_held_samples().iter().map(sample_json).collect())),
+   ])
+}
+
+pub fn state_json(state: &history::TurnState, projects: &[molecules::Molecules]) -> json::Json {
+   json::Json::Object(vec![
+      ("me", robot_json(state.get_my_robot())),
+      ("enemy", robot_json(state.get_enemy_robot())),
+      ("available", molecules_json(state.get_available())),
+      ("cloud", json::Json::Array(state.get_cloud().iter().map(sample_json).collect())),
+      ("projects", json::Json::Array(projects.iter().map(molecules_json).collect())),
+   ])
+}
+
+pub fn turn_json(
+   state: &history::TurnState,
+   projects: &[molecules::Molecules],
+   strategy: &str,
+   goal: Option<&str>,
+   output: &command::Output,
+   elapsed: Duration,
+) -> json::Json {
+   let position = notation::Position::new(state.clone(), projects.to_vec());
+   json::Json::Object(vec![
+      ("turn", json::Json::Number(state.get_turn() as i64)),
+      ("strategy", json::Json::string(strategy)),
+      ("goal", json::Json::optional(goal.map(json::Json::string))),
+      ("command", json::Json::String(output.get_action().to_string())),
+      ("message", json::Json::optional(output.get_message().map(json::Json::string))),
+      ("elapsed_us", json::Json::Number(elapsed.as_micros() as i64)),
+      ("position", json::Json::String(position.to_string())),
+      ("state", state_json(state, projects)),
+   ])
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::module::Module;
+   use super::super::notation::Position;
+   use super::turn_json;
+   use std::time::Duration;
+
+   #[test]
+   fn exports_a_turn() {
+      let position = "3; SAMPLES 0 0 - -; DIAGNOSIS 1 10 A1 -; A4B5C5D5E5; A3B3C3; 5:m:1:?:?:A 6:c:2:20:B3D2:E"
+         .parse::<Position>()
+         .unwrap();
+      let output = Command::Goto(Module::Diagnosis).with_message("DIAGNOSE s5");
+      let record = turn_json(
+         position.get_state(),
+         position.get_projects(),
+         "goal_machine",
+         Some("ResearchSamples"),
+         &output,
+         Duration::from_micros(85),
+      )
+      .to_string();
+      let expected = concat!(
+         r#"{"turn":3,"strategy":"goal_machine","goal":"ResearchSamples","command":"GOTO DIAGNOSIS","#,
+         r#""message":"DIAGNOSE s5","elapsed_us":85,"#,
+         r#""position":"3; SAMPLES 0 0 - -; DIAGNOSIS 1 10 A1 -; A4B5C5D5E5; A3B3C3; 5:m:1:?:?:A 6:c:2:20:B3D2:E","#,
+         r#""state":{"me":{"module":"SAMPLES","eta":0,"score":0,"inventory":[0,0,0,0,0],"expertise":[0,0,0,0,0],"#,
+         r#""samples":[{"id":5,"rank":1,"health":null,"cost":null,"gain":"A"}]},"#,
+         r#""enemy":{"module":"DIAGNOSIS","eta":1,"score":10,"inventory":[1,0,0,0,0],"expertise":[0,0,0,0,0],"samples":[]},"#,
+         r#""available":[4,5,5,5,5],"cloud":[{"id":6,"rank":2,"health":20,"cost":[0,3,0,2,0],"gain":"E"}],"#,
+         r#""projects":[[3,3,3,0,0]]}}"#
+      );
+      assert_eq!(record, expected);
+   }
+}
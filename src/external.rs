@@ -0,0 +1,143 @@
+/*!
+ * Another build of a bot, run as a child process and spoken to with the arena
+ * protocol, so it can stand in for a strategy in simulations.
+ */
+
+use super::command;
+use super::game;
+use super::strategy;
+use super::time_budget;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::process;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct ExternalStrategy {
+   child: process::Child,
+   // lines printed by the bot, read on a helper thread so that waiting for one can time out
+   lines: mpsc::Receiver<std::io::Result<String>>,
+   first_turn_limit: Duration,
+   turn_limit: Duration,
+   started: bool,
+}
+
+impl ExternalStrategy {
+   pub const NAME: &'static str = "external";
+
+   // `arguments` go to the bot, e.g. the name of one of its strategies
+   pub fn spawn(path: &str, arguments: &[&str]) -> std::io::Result<Self> {
+      let mut child = process::Command::new(path)
+         .args(arguments)
+         .stdin(process::Stdio::piped())
+         .stdout(process::Stdio::piped())
+         .stderr(process::Stdio::null())
+         .spawn()?;
+      let mut output = BufReader::new(child.stdout.take().ok_or(std::io::ErrorKind::BrokenPipe)?);
+      let (sender, lines) = mpsc::channel();
+      thread::spawn(move || loop {
+         let mut line = String::new();
+         let read = match output.read_line(&mut line) {
+            Ok(0) => Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => Ok(line),
+            Err(error) => Err(error),
+         };
+         let failed = read.is_err();
+         if sender.send(read).is_err() || failed {
+            break;
+         }
+      });
+      Ok(Self {
+         child,
+         lines,
+         first_turn_limit: time_budget::TimeBudget::FIRST_TURN_LIMIT,
+         turn_limit: time_budget::TimeBudget::TURN_LIMIT,
+         started: false,
+      })
+   }
+
+   // the arena limits by default, a slow build of the bot may need more
+   pub fn with_time_limits(mut self, first_turn_limit: Duration, turn_limit: Duration) -> Self {
+      self.first_turn_limit = first_turn_limit;
+      self.turn_limit = turn_limit;
+      self
+   }
+
+   fn exchange(&mut self, input: &str, limit: Duration) -> std::io::Result<String> {
+      let stdin = self.child.stdin.as_mut().ok_or(std::io::ErrorKind::BrokenPipe)?;
+      stdin.write_all(input.as_bytes())?;
+      stdin.flush()?;
+      match self.lines.recv_timeout(limit) {
+         Ok(line) => line,
+         Err(mpsc::RecvTimeoutError::Timeout) => {
+            // a late answer would be taken for the next turn's, so the bot is out
+            let _ = self.child.kill();
+            Err(std::io::ErrorKind::TimedOut.into())
+         }
+         Err(mpsc::RecvTimeoutError::Disconnected) => Err(std::io::ErrorKind::UnexpectedEof.into()),
+      }
+   }
+}
+
+impl Drop for ExternalStrategy {
+   fn drop(&mut self) {
+      let _ = self.child.kill();
+      let _ = self.child.wait();
+   }
+}
+
+impl strategy::Strategy for ExternalStrategy {
+   fn name(&self) -> &'static str {
+      Self::NAME
+   }
+
+   // a bot that crashed, printed nonsense or ran out of time just waits from then on
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      let state = context.get_state();
+      let mut input = String::new();
+      let limit = if self.started { self.turn_limit } else { self.first_turn_limit };
+      if !self.started {
+         input.push_str(&game::format_initial_input(context.get_tables().get_projects()));
+         self.started = true;
+      }
+      input.push_str(&game::format_turn_input(state));
+      match self.exchange(&input, limit) {
+         Ok(line) => command::Output::parse_at(&line, state.get_my_robot().get_location()).unwrap_or(command::Command::Wait.into()),
+         Err(error) => {
+            eprintln!("external bot failed: {}", error);
+            command::Command::Wait.into()
+         }
+      }
+   }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+   use super::super::command::Command;
+   use super::super::history::{History, TurnState};
+   use super::super::module::Module;
+   use super::super::scenario::{molecules, RobotBuilder};
+   use super::super::strategy::{Strategy, TurnContext};
+   use super::super::tables::LookupTables;
+   use super::super::time_budget::TimeBudget;
+   use super::ExternalStrategy;
+   use std::time::{Duration, Instant};
+
+   #[test]
+   fn a_silent_bot_times_out_and_waits() {
+      let limit = Duration::from_millis(50);
+      let mut bot = ExternalStrategy::spawn("sh", &["-c", "cat > /dev/null"]).unwrap().with_time_limits(limit, limit);
+      let state = TurnState::new(0, RobotBuilder::at(Module::Spawn).build(), RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules(""));
+      let (history, tables) = (History::new(), LookupTables::new(Vec::new()));
+      let budget = TimeBudget::for_turn(Instant::now(), 0);
+      for _ in 0..2 {
+         let started = Instant::now();
+         let output = bot.decide(&TurnContext::new(&state, &history, &tables, &budget));
+         assert_eq!(output.get_action(), &Command::Wait);
+         assert!(started.elapsed() < Duration::from_secs(5));
+      }
+   }
+}
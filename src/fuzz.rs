@@ -0,0 +1,128 @@
+/*!
+ * Random positions that respect the rules without looking like real play:
+ * full inventories, depleted stocks, robots halfway between modules, held
+ * samples with any cost. Seeded, so a failure is reproduced by its seed.
+ */
+
+use super::history;
+use super::module;
+use super::molecules;
+use super::notation;
+use super::random;
+use super::robot;
+use super::sample;
+
+const STOCK_PER_TYPE: i8 = 5;
+const MAX_MOLECULES: i16 = 10;
+const MAX_SAMPLES: usize = 3;
+const MAX_CLOUD_SAMPLES: usize = 6;
+const MAX_COST_PER_TYPE: u64 = 7;
+const MAX_EXPERTISE: u64 = 8;
+const MAX_ETA: u64 = 4;
+const HEALTHS: [u8; 6] = [1, 10, 20, 30, 40, 50];
+
+#[derive(Debug)]
+pub struct PositionGenerator {
+   random: random::Random,
+   next_sample_id: u8,
+}
+
+impl PositionGenerator {
+   pub fn new(seed: u64) -> Self {
+      Self {
+         random: random::Random::new(seed),
+         next_sample_id: 0,
+      }
+   }
+
+   // uniform in 0..=high
+   fn up_to(&mut self, high: u64) -> u64 {
+      self.random.below(high as usize + 1) as u64
+   }
+
+   fn sample(&mut self) -> sample::Sample {
+      let rank = sample::SampleRank::ALL[self.up_to(2) as usize];
+      let gain = molecules::Molecules::from_letter(molecules::Molecule::ALL[self.up_to(4) as usize].as_char());
+      let id = self.next_sample_id;
+      self.next_sample_id += 1 + self.up_to(3) as u8;
+      if self.up_to(2) == 0 {
+         let unknown = molecules::Molecules::from_array([-1; 5]);
+         return sample::Sample::new(id, rank, sample::SampleHealth::Unresearched, unknown, gain);
+      }
+      let mut cost = molecules::Molecules::new();
+      for molecule in molecules::Molecule::ALL {
+         // mostly cheap types, sometimes the most a sample can ask for
+         cost[molecule] = if self.up_to(2) == 0 { self.up_to(MAX_COST_PER_TYPE) as i8 } else { 0 };
+      }
+      let health = sample::SampleHealth::Researched(HEALTHS[self.up_to(HEALTHS.len() as u64 - 1) as usize]);
+      sample::Sample::new(id, rank, health, cost, gain)
+   }
+
+   fn robot(&mut self, inventory: molecules::Molecules) -> robot::Robot {
+      let location = module::Module::ALL[self.up_to(module::Module::ALL.len() as u64 - 1) as usize];
+      let eta = if self.up_to(2) == 0 { self.up_to(MAX_ETA) as u8 } else { 0 };
+      let mut expertise = molecules::Molecules::new();
+      for molecule in molecules::Molecule::ALL {
+         expertise[molecule] = self.up_to(MAX_EXPERTISE) as i8;
+      }
+      let samples = (0..self.up_to(MAX_SAMPLES as u64)).map(|_| self.sample()).collect();
+      robot::Robot::from_parts(location, eta, self.up_to(400) as i16, inventory, expertise, samples)
+   }
+
+   fn project(&mut self) -> molecules::Molecules {
+      let (types, required) = if self.up_to(1) == 0 { (2, 4) } else { (3, 3) };
+      let mut project = molecules::Molecules::new();
+      while project.len() < types * required {
+         project[molecules::Molecule::ALL[self.up_to(4) as usize]] = required as i8;
+      }
+      project
+   }
+
+   /**
+    * The next position: every molecule type split between the stock and the
+    * two inventories, at most 10 carried, at most 3 samples held.
+    */
+   pub fn next_position(&mut self) -> notation::Position {
+      self.next_sample_id = self.up_to(20) as u8;
+      let mut inventories = [molecules::Molecules::new(), molecules::Molecules::new()];
+      let mut available = molecules::Molecules::new();
+      for molecule in molecules::Molecule::ALL {
+         let mine = self.up_to(STOCK_PER_TYPE as u64) as i8;
+         let theirs = self.up_to((STOCK_PER_TYPE - mine) as u64) as i8;
+         inventories[0][molecule] = mine;
+         inventories[1][molecule] = theirs;
+         available[molecule] = STOCK_PER_TYPE - mine - theirs;
+      }
+      for inventory in inventories.iter_mut() {
+         while inventory.len() > MAX_MOLECULES {
+            let molecule = inventory.max_molecule();
+            inventory[molecule] -= 1;
+            available[molecule] += 1;
+         }
+      }
+      let [mine, theirs] = inventories;
+      let my_robot = self.robot(mine);
+      let enemy_robot = self.robot(theirs);
+      let cloud = (0..self.up_to(MAX_CLOUD_SAMPLES as u64)).map(|_| self.sample()).collect();
+      let turn = self.up_to(199) as u16;
+      let projects = (0..self.up_to(3)).map(|_| self.project()).collect();
+      notation::Position::new(history::TurnState::new(turn, my_robot, enemy_robot, cloud, available), projects)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::validator;
+   use super::PositionGenerator;
+
+   #[test]
+   fn generates_valid_reproducible_positions() {
+      let mut generator = PositionGenerator::new(7);
+      let mut again = PositionGenerator::new(7);
+      for _ in 0..200 {
+         let position = generator.next_position();
+         assert_eq!(validator::validate_state(position.get_state()), Vec::new());
+         assert_eq!(position.to_string(), again.next_position().to_string());
+      }
+   }
+}
@@ -0,0 +1,526 @@
+/*!
+ * The Code4Life rules, so games can be played without the arena.
+ *
+ * Players see the game through the same protocol text the arena sends, and
+ * `state_for` goes through `input_reading` to build their `TurnState`, so a
+ * simulated turn looks exactly like a live one.
+ *
+ * The official sample deck and project list are not public; samples and
+ * projects are drawn from a seeded generator that follows their shape
+ * (rank 1 samples cost 3 to 5 molecules for 1 or 10 health, rank 3 ones up to
+ * 14 molecules for 50 health, projects ask for 4+4 or 3+3+3 expertise).
+ */
+
+use super::carried_by;
+use super::command;
+use super::connect_options;
+use super::history;
+use super::input_reading;
+use super::module;
+use super::molecules;
+use super::random;
+use super::robot;
+use super::sample;
+use super::tables;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::Cursor;
+
+pub const PLAYERS: usize = 2;
+
+#[derive(Clone, Debug)]
+pub struct Game {
+   robots: [robot::Robot; PLAYERS],
+   available: molecules::Molecules,
+   cloud: Vec<sample::Sample>,
+   projects: Vec<molecules::Molecules>,
+   completed_projects: Vec<bool>,
+   // diagnosed values of every sample in play, robots only see them once diagnosed
+   diagnosed: HashMap<u8, sample::Sample>,
+   next_sample_id: u8,
+   random: random::Random,
+   turn: u16,
+   lost: [bool; PLAYERS],
+   tables: tables::LookupTables,
+}
+
+impl Game {
+   pub const MAX_TURNS: u16 = 200;
+   const INITIAL_STOCK: i8 = 5;
+   const PROJECT_COUNT: usize = 3;
+   const PROJECT_POINTS: i16 = 50;
+   const MAX_COST_PER_TYPE: i8 = 7;
+
+   pub fn new(seed: u64) -> Self {
+      Self::with_random(random::Random::new(seed))
+   }
+
+   // a game drawing from `random`, e.g. a split of a simulation's generator
+   pub fn with_random(random: random::Random) -> Self {
+      let mut game = Self {
+         robots: [robot::Robot::new(), robot::Robot::new()],
+         available: molecules::Molecules::from_array([Self::INITIAL_STOCK; 5]),
+         cloud: Vec::new(),
+         projects: Vec::new(),
+         completed_projects: Vec::new(),
+         diagnosed: HashMap::new(),
+         next_sample_id: 0,
+         random,
+         turn: 0,
+         lost: [false; PLAYERS],
+         tables: tables::LookupTables::new(Vec::new()),
+      };
+      for _ in 0..Self::PROJECT_COUNT {
+         let project = game.generate_project();
+         game.projects.push(project);
+      }
+      game.completed_projects = vec![false; Self::PROJECT_COUNT];
+      game
+   }
+
+   pub fn get_turn(&self) -> u16 {
+      self.turn
+   }
+   pub fn get_robot(&self, player: usize) -> &robot::Robot {
+      &self.robots[player]
+   }
+   pub fn get_available(&self) -> &molecules::Molecules {
+      &self.available
+   }
+   pub fn get_cloud(&self) -> &Vec<sample::Sample> {
+      &self.cloud
+   }
+   pub fn get_projects(&self) -> &Vec<molecules::Molecules> {
+      &self.projects
+   }
+   pub fn has_lost(&self, player: usize) -> bool {
+      self.lost[player]
+   }
+
+   pub fn get_scores(&self) -> [i16; PLAYERS] {
+      [self.robots[0].get_score(), self.robots[1].get_score()]
+   }
+
+   pub fn is_over(&self) -> bool {
+      self.turn >= Self::MAX_TURNS || self.lost.iter().any(|lost| *lost)
+   }
+
+   // players from first to last, tied players share a group
+   pub fn ranking(&self) -> Vec<Vec<usize>> {
+      let key = |player: usize| (!self.lost[player], self.robots[player].get_score());
+      let (first, second) = (key(0), key(1));
+      if first == second {
+         vec![vec![0, 1]]
+      } else if first > second {
+         vec![vec![0], vec![1]]
+      } else {
+         vec![vec![1], vec![0]]
+      }
+   }
+
+   // what the arena sends once before the first turn
+   pub fn initial_input(&self) -> String {
+      format_initial_input(&self.projects)
+   }
+
+   // the turn from the point of view of `player`, the way the arena shows it
+   pub fn view(&self, player: usize) -> history::TurnState {
+      let enemy = PLAYERS - 1 - player;
+      history::TurnState::new(
+         self.turn,
+         self.robots[player].clone(),
+         self.robots[enemy].clone(),
+         self.cloud.clone(),
+         self.available.clone(),
+      )
+   }
+
+   // what the arena sends `player` at the start of every turn
+   pub fn turn_input(&self, player: usize) -> String {
+      format_turn_input(&self.view(player))
+   }
+
+   // the turn as `player` would parse it from the arena
+   pub fn state_for(&self, player: usize) -> history::TurnState {
+      let input = self.turn_input(player);
+      let (my_robot, enemy_robot, cloud, available, _) = input_reading::parse_turn_input_from(&mut Cursor::new(input.as_str()));
+      history::TurnState::new(self.turn, my_robot, enemy_robot, cloud, available)
+   }
+
+   /**
+    * Plays one turn for both players at once. `None` is an output the arena
+    * could not understand, which loses the game; legal looking commands the
+    * rules refuse are ignored, like the arena does.
+    */
+   pub fn play(&mut self, commands: [Option<command::Command>; PLAYERS]) {
+      let mut actions = [None, None];
+      for (player, command) in commands.into_iter().enumerate() {
+         match command {
+            None => self.lost[player] = true,
+            Some(command) => {
+               let robot = &self.robots[player];
+               if robot.get_eta() == 0 && command.is_legal(robot, &self.available, &self.cloud) {
+                  actions[player] = Some(command);
+               }
+            }
+         }
+      }
+      for robot in self.robots.iter_mut() {
+         robot.advance();
+      }
+
+      // both asking for the last molecule or the same cloud sample get nothing
+      let wanted = |options: &connect_options::ConnectOptions| {
+         actions.iter().flatten().filter(|action| **action == command::Command::Connect(options.clone())).count()
+      };
+      let mut contested = [false; PLAYERS];
+      for (player, action) in actions.iter().enumerate() {
+         contested[player] = match action {
+            Some(command::Command::Connect(options @ connect_options::ConnectOptions::MoleculeType(molecule))) => {
+               wanted(options) as i8 > self.available[*molecule]
+            }
+            Some(command::Command::Connect(options @ connect_options::ConnectOptions::SampleId(id))) => {
+               self.cloud.iter().any(|sample| sample.get_id() == *id) && wanted(options) > 1
+            }
+            _ => false,
+         };
+      }
+
+      let mut returned = molecules::Molecules::new();
+      for (player, action) in actions.into_iter().enumerate() {
+         match action {
+            Some(_) if contested[player] => {}
+            Some(command::Command::Goto(target)) => {
+               let distance = self.tables.distance(self.robots[player].get_location(), &target);
+               self.robots[player].start_moving(target, distance.saturating_sub(1));
+            }
+            Some(command::Command::Connect(options)) => {
+               returned = &returned + &self.connect(player, options);
+            }
+            _ => {}
+         }
+      }
+      self.available = &self.available + &returned;
+      self.complete_projects();
+      self.turn += 1;
+   }
+
+   // returns the molecules spent at the laboratory
+   fn connect(&mut self, player: usize, options: connect_options::ConnectOptions) -> molecules::Molecules {
+      let location = *self.robots[player].get_location();
+      match (location, options) {
+         (module::Module::Sample, connect_options::ConnectOptions::SampleRank(rank)) => {
+            let drawn = self.draw_sample(rank);
+            self.robots[player].append_sample(drawn);
+         }
+         (module::Module::Diagnosis, connect_options::ConnectOptions::SampleId(id)) => {
+            if let Some(held) = self.robots[player].remove_sample(id) {
+               match held.get_health() {
+                  sample::SampleHealth::Unresearched => {
+                     let diagnosed = self.diagnosed[&id].clone();
+                     self.robots[player].append_sample(diagnosed);
+                  }
+                  sample::SampleHealth::Researched(_) => self.cloud.push(held),
+               }
+            } else if let Some(position) = self.cloud.iter().position(|sample| sample.get_id() == id) {
+               let taken = self.cloud.remove(position);
+               self.robots[player].append_sample(taken);
+            }
+         }
+         (module::Module::Molecule, connect_options::ConnectOptions::MoleculeType(molecule)) => {
+            self.available[molecule] -= 1;
+            self.robots[player].take_molecule(molecule);
+         }
+         (module::Module::Laboratory, connect_options::ConnectOptions::SampleId(id)) => {
+            if let Some(produced) = self.robots[player].remove_sample(id) {
+               self.diagnosed.remove(&id);
+               return self.robots[player].produce(&produced);
+            }
+         }
+         _ => {}
+      }
+      molecules::Molecules::new()
+   }
+
+   // every robot reaching a project the same turn scores it
+   fn complete_projects(&mut self) {
+      for (index, project) in self.projects.iter().enumerate() {
+         if self.completed_projects[index] {
+            continue;
+         }
+         for robot in self.robots.iter_mut() {
+            if (project - robot.get_expertise()).is_not_positive() {
+               robot.add_score(Self::PROJECT_POINTS);
+               self.completed_projects[index] = true;
+            }
+         }
+      }
+   }
+
+   // `count` distinct molecule types
+   fn random_types(&mut self, count: usize) -> Vec<molecules::Molecule> {
+      let mut types = molecules::Molecule::ALL.to_vec();
+      self.random.shuffle(&mut types);
+      types.truncate(count);
+      types
+   }
+
+   fn generate_project(&mut self) -> molecules::Molecules {
+      let (types, required) = if self.random.below(2) == 0 { (2, 4) } else { (3, 3) };
+      let mut project = molecules::Molecules::new();
+      for molecule in self.random_types(types) {
+         project[molecule] = required;
+      }
+      project
+   }
+
+   fn draw_sample(&mut self, rank: sample::SampleRank) -> sample::Sample {
+      let (min_total, max_total) = match rank {
+         sample::SampleRank::LittleHealth => (3, 5),
+         sample::SampleRank::SomeHealth => (5, 8),
+         sample::SampleRank::LotsOfHealth => (7, 14),
+      };
+      let total = min_total + self.random.below((max_total - min_total + 1) as usize) as i8;
+      let health = match (rank, total) {
+         (sample::SampleRank::LittleHealth, 5) => 10,
+         (sample::SampleRank::LittleHealth, _) => 1,
+         (sample::SampleRank::SomeHealth, 5..=6) => 10,
+         (sample::SampleRank::SomeHealth, 7) => 20,
+         (sample::SampleRank::SomeHealth, _) => 30,
+         (sample::SampleRank::LotsOfHealth, 7..=9) => 30,
+         (sample::SampleRank::LotsOfHealth, 10..=12) => 40,
+         (sample::SampleRank::LotsOfHealth, _) => 50,
+      };
+      let fewest_types = ((total + Self::MAX_COST_PER_TYPE - 1) / Self::MAX_COST_PER_TYPE) as usize;
+      let type_count = (1 + self.random.below(4)).max(fewest_types);
+      let types = self.random_types(type_count);
+      let mut cost = molecules::Molecules::new();
+      for molecule in types.iter() {
+         cost[*molecule] = 1;
+      }
+      let mut left = total - type_count as i8;
+      while left > 0 {
+         let molecule = types[self.random.below(types.len())];
+         if cost[molecule] < Self::MAX_COST_PER_TYPE {
+            cost[molecule] += 1;
+            left -= 1;
+         }
+      }
+      let gain = molecules::Molecule::ALL[self.random.below(5)];
+
+      let id = self.next_free_sample_id();
+      let diagnosed = sample::Sample::new(
+         id,
+         rank,
+         sample::SampleHealth::Researched(health),
+         cost,
+         molecules::Molecules::from_letter(gain.as_char()),
+      );
+      self.diagnosed.insert(id, diagnosed);
+      sample::Sample::new(
+         id,
+         rank,
+         sample::SampleHealth::Unresearched,
+         molecules::Molecules::from_array([-1; 5]),
+         molecules::Molecules::from_letter(gain.as_char()),
+      )
+   }
+
+   // ids only have to be unique among the samples in play
+   fn next_free_sample_id(&mut self) -> u8 {
+      loop {
+         let id = self.next_sample_id;
+         self.next_sample_id = self.next_sample_id.wrapping_add(1);
+         let in_cloud = self.cloud.iter().any(|sample| sample.get_id() == id);
+         if !self.diagnosed.contains_key(&id) && !in_cloud {
+            return id;
+         }
+      }
+   }
+}
+
+fn counts(molecules: &molecules::Molecules) -> String {
+   molecules.as_array().iter().map(|count| count.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+// the project lines, as `input_reading::parse_projects_from` reads them
+pub fn format_initial_input(projects: &[molecules::Molecules]) -> String {
+   let mut text = format!("{}\n", projects.len());
+   for project in projects {
+      let _ = writeln!(text, "{}", counts(project));
+   }
+   text
+}
+
+// the turn lines, as `input_reading::parse_turn_input_from` reads them
+pub fn format_turn_input(state: &history::TurnState) -> String {
+   let mut text = String::new();
+   for robot in [state.get_my_robot(), state.get_enemy_robot()] {
+      let _ = writeln!(
+         text,
+         "{} {} {} {} {}",
+         robot.get_location().as_str(),
+         robot.get_eta(),
+         robot.get_score(),
+         counts(robot.get_inventory()),
+         counts(robot.get_expertise())
+      );
+   }
+   let _ = writeln!(text, "{}", counts(state.get_available()));
+   let mut samples = Vec::new();
+   for (owner, robot) in [
+      (carried_by::CarriedBy::Me, state.get_my_robot()),
+      (carried_by::CarriedBy::Other, state.get_enemy_robot()),
+   ] {
+      samples.extend(robot.get_held_samples().iter().map(|sample| (owner, sample)));
+   }
+   samples.extend(state.get_cloud().iter().map(|sample| (carried_by::CarriedBy::Cloud, sample)));
+   let _ = writeln!(text, "{}", samples.len());
+   for (owner, sample) in samples {
+      let health = match sample.get_health() {
+         sample::SampleHealth::Researched(health) => *health as i16,
+         sample::SampleHealth::Unresearched => -1,
+      };
+      let gain = sample
+         .get_expertise_gain()
+         .iter()
+         .find(|(_, count)| *count > 0)
+         .map_or('0', |(molecule, _)| molecule.as_char());
+      let _ = writeln!(
+         text,
+         "{} {} {} {} {} {}",
+         sample.get_id(),
+         owner as i8,
+         sample.get_rank().as_value(),
+         gain,
+         health,
+         counts(sample.get_cost())
+      );
+   }
+   text
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::connect_options::ConnectOptions;
+   use super::super::module::Module;
+   use super::super::molecules::{Molecule, Molecules};
+   use super::super::sample::{SampleHealth, SampleRank};
+   use super::super::validator;
+   use super::Game;
+
+   fn run(game: &mut Game, first: &str, second: &str) {
+      let parse = |player: usize, text: &str| Command::parse_at(text, game.get_robot(player).get_location()).ok();
+      let commands = [parse(0, first), parse(1, second)];
+      game.play(commands);
+   }
+
+   fn wait_until_arrived(game: &mut Game) {
+      while game.get_robot(0).get_eta() > 0 || game.get_robot(1).get_eta() > 0 {
+         run(game, "WAIT", "WAIT");
+      }
+   }
+
+   #[test]
+   fn same_seed_same_game() {
+      let (mut first, mut second) = (Game::new(7), Game::new(7));
+      assert_eq!(first.initial_input(), second.initial_input());
+      for game in [&mut first, &mut second] {
+         run(game, "GOTO SAMPLES", "GOTO SAMPLES");
+         wait_until_arrived(game);
+         run(game, "CONNECT 3", "CONNECT 1");
+         run(game, "GOTO DIAGNOSIS", "GOTO DIAGNOSIS");
+         wait_until_arrived(game);
+         let (mine, theirs) = (game.get_robot(0).get_held_samples()[0].get_id(), game.get_robot(1).get_held_samples()[0].get_id());
+         run(game, &format!("CONNECT {}", mine), &format!("CONNECT {}", theirs));
+      }
+      assert_eq!(first.turn_input(0), second.turn_input(0));
+      assert_eq!(first.turn_input(1), second.turn_input(1));
+      assert_ne!(Game::new(8).initial_input(), first.initial_input());
+   }
+
+   #[test]
+   fn plays_a_production_cycle_within_the_rules() {
+      let mut game = Game::new(41);
+      let mut previous = game.state_for(0);
+      run(&mut game, "GOTO SAMPLES", "WAIT");
+      assert_eq!(game.get_robot(0).get_eta(), 1);
+      wait_until_arrived(&mut game);
+      run(&mut game, "CONNECT 1", "WAIT");
+      let id = game.get_robot(0).get_held_samples()[0].get_id();
+      assert_eq!(game.get_robot(0).get_held_samples()[0].get_health(), &SampleHealth::Unresearched);
+
+      run(&mut game, "GOTO DIAGNOSIS", "WAIT");
+      wait_until_arrived(&mut game);
+      run(&mut game, &format!("CONNECT {}", id), "WAIT");
+      let sample = game.get_robot(0).get_held_samples()[0].clone();
+      assert_eq!(sample.get_rank(), &SampleRank::LittleHealth);
+      assert!((3..=5).contains(&sample.get_cost().len()));
+
+      run(&mut game, "GOTO MOLECULES", "WAIT");
+      wait_until_arrived(&mut game);
+      for (molecule, count) in sample.get_cost().iter() {
+         for _ in 0..count {
+            run(&mut game, &format!("CONNECT {}", molecule), "WAIT");
+         }
+      }
+      assert_eq!(game.get_robot(0).get_inventory(), sample.get_cost());
+      run(&mut game, "GOTO LABORATORY", "WAIT");
+      wait_until_arrived(&mut game);
+      run(&mut game, &format!("CONNECT {}", id), "WAIT");
+
+      let robot = game.get_robot(0);
+      let health = match sample.get_health() {
+         SampleHealth::Researched(health) => *health as i16,
+         SampleHealth::Unresearched => 0,
+      };
+      assert_eq!(robot.get_score(), health);
+      assert_eq!(robot.get_expertise(), sample.get_expertise_gain());
+      assert!(robot.get_held_samples().is_empty());
+      assert_eq!(game.get_available(), &Molecules::from_array([5; 5]));
+
+      for _ in 0..3 {
+         let current = game.state_for(0);
+         assert_eq!(validator::validate(Some(&previous), &current), vec![]);
+         previous = current;
+      }
+   }
+
+   #[test]
+   fn contested_and_illegal_actions_do_nothing() {
+      let mut game = Game::new(3);
+      run(&mut game, "GOTO MOLECULES", "GOTO MOLECULES");
+      wait_until_arrived(&mut game);
+      for _ in 0..4 {
+         run(&mut game, "CONNECT A", "CONNECT B");
+      }
+      run(&mut game, "CONNECT A", "CONNECT A");
+      assert_eq!(game.get_robot(0).get_inventory()[Molecule::A], 4);
+      assert_eq!(game.get_available()[Molecule::A], 1);
+
+      run(&mut game, "CONNECT 1", "GOTO START_POS");
+      assert_eq!(game.get_robot(0).get_held_samples().len(), 0);
+      assert_eq!(game.get_robot(1).get_location(), &Module::Molecule);
+      assert!(!game.is_over());
+
+      game.play([Some(Command::Connect(ConnectOptions::MoleculeType(Molecule::A))), None]);
+      assert!(game.has_lost(1));
+      assert!(game.is_over());
+      assert_eq!(game.ranking(), vec![vec![0], vec![1]]);
+   }
+
+   #[test]
+   fn turn_input_is_from_the_players_point_of_view() {
+      let mut game = Game::new(5);
+      run(&mut game, "WAIT", "GOTO SAMPLES");
+      wait_until_arrived(&mut game);
+      run(&mut game, "WAIT", "CONNECT 2");
+      let seen_by_first = game.state_for(0);
+      let seen_by_second = game.state_for(1);
+      assert_eq!(seen_by_first.get_enemy_robot().get_held_samples().len(), 1);
+      assert_eq!(seen_by_second.get_my_robot().get_held_samples().len(), 1);
+      assert_eq!(seen_by_second.get_my_robot().get_location(), &Module::Sample);
+      assert_eq!(game.initial_input().lines().count(), 4);
+   }
+}
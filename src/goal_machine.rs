@@ -0,0 +1,385 @@
+use super::command;
+use super::connect_options;
+use super::history;
+use super::module;
+use super::molecules;
+use super::pickup_policy;
+use super::sample;
+use super::strategy;
+use std::str::FromStr;
+
+#[derive(Debug)]
+enum GameGoals {
+   TakeSamples,
+   ResearchSamples,
+   GatherMolecules,
+   ProduceMedicine,
+   DropSamples,
+}
+
+impl GameGoals {
+   fn as_str(&self) -> &'static str {
+      match self {
+         GameGoals::TakeSamples => "TakeSamples",
+         GameGoals::ResearchSamples => "ResearchSamples",
+         GameGoals::GatherMolecules => "GatherMolecules",
+         GameGoals::ProduceMedicine => "ProduceMedicine",
+         GameGoals::DropSamples => "DropSamples",
+      }
+   }
+}
+
+impl FromStr for GameGoals {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      match s {
+         "TakeSamples" => Ok(GameGoals::TakeSamples),
+         "ResearchSamples" => Ok(GameGoals::ResearchSamples),
+         "GatherMolecules" => Ok(GameGoals::GatherMolecules),
+         "ProduceMedicine" => Ok(GameGoals::ProduceMedicine),
+         "DropSamples" => Ok(GameGoals::DropSamples),
+         _ => Err("Unknown goal"),
+      }
+   }
+}
+
+/**
+ * The original bot: walks the SAMPLES -> DIAGNOSIS -> MOLECULES -> LABORATORY
+ * loop, switching goals whenever the current one is done or impossible.
+ */
+#[derive(Debug)]
+pub struct GoalMachineStrategy {
+   goal: GameGoals,
+   speculative_gathering: bool,
+   // goal changes during the current decision
+   switches: u8,
+   error: Option<&'static str>,
+}
+
+impl Default for GoalMachineStrategy {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+impl strategy::Strategy for GoalMachineStrategy {
+   fn name(&self) -> &'static str {
+      if self.speculative_gathering {
+         Self::SPECULATIVE_NAME
+      } else {
+         Self::NAME
+      }
+   }
+
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      let state = context.get_state();
+      self.switches = 0;
+      match self.decide_goal(state) {
+         Ok(command) => {
+            self.error = None;
+            command.with_message(&self.describe_goal(state))
+         }
+         Err(error) => {
+            self.error = Some(error);
+            command::Command::Wait.into()
+         }
+      }
+   }
+
+   fn get_goal(&self) -> Option<&'static str> {
+      Some(self.goal.as_str())
+   }
+
+   fn set_goal(&mut self, goal: &str) -> Result<(), &'static str> {
+      self.goal = goal.parse()?;
+      Ok(())
+   }
+
+   fn get_error(&self) -> Option<&'static str> {
+      self.error
+   }
+}
+
+impl GoalMachineStrategy {
+   pub const NAME: &'static str = "goal_machine";
+   pub const SPECULATIVE_NAME: &'static str = "goal_machine_speculative";
+   // enough to visit every goal once and come back
+   const MAX_GOAL_SWITCHES: u8 = 6;
+
+   pub fn new() -> Self {
+      Self {
+         goal: GameGoals::TakeSamples,
+         speculative_gathering: false,
+         switches: 0,
+         error: None,
+      }
+   }
+
+   // spend spare molecule capacity on cloud samples and likely future costs
+   pub fn with_speculative_gathering(mut self) -> Self {
+      self.speculative_gathering = true;
+      self
+   }
+
+   // short speech bubble for replays, e.g. "GATHER s12 need B2 E1"
+   fn describe_goal(&self, state: &history::TurnState) -> String {
+      let my_robot = state.get_my_robot();
+      let sample_id = |sample: Option<&sample::Sample>| match sample {
+         Some(sample) => format!(" s{}", sample.get_id()),
+         None => String::new(),
+      };
+      match self.goal {
+         GameGoals::TakeSamples => format!("TAKE r{}", my_robot.pick_sample_based_on_expertise().as_value()),
+         GameGoals::ResearchSamples => format!("DIAGNOSE{}", sample_id(my_robot.get_unresearched_sample())),
+         GameGoals::GatherMolecules => {
+            let covered = my_robot.get_inventory() + my_robot.get_expertise();
+            let target = my_robot
+               .get_sorted_samples()
+               .into_iter()
+               .find(|sample| !(sample.get_cost() - &covered).is_not_positive());
+            match target {
+               Some(sample) => {
+                  let needed = (sample.get_cost() - &covered).set_minues_to_zero();
+                  format!("GATHER{} need {}", sample_id(Some(sample)), needed)
+               }
+               None => "GATHER".to_owned(),
+            }
+         }
+         GameGoals::ProduceMedicine => {
+            format!("PRODUCE{}", sample_id(my_robot.get_most_interesting_ready_sample()))
+         }
+         GameGoals::DropSamples => {
+            let impossible = my_robot.get_impossible_samples(state.get_available());
+            format!("DROP{}", sample_id(impossible.first().copied()))
+         }
+      }
+   }
+
+   /**
+    * Moves on to `goal` and decides from there. Every goal hands over to
+    * another when it has nothing to do, so positions no goal wants would
+    * go round forever; past `MAX_GOAL_SWITCHES` in a turn that is an error.
+    */
+   fn switch_goal(&mut self, goal: GameGoals, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      self.switches += 1;
+      if self.switches > Self::MAX_GOAL_SWITCHES {
+         return Err("goals kept handing over to each other without a command");
+      }
+      self.goal = goal;
+      self.decide_goal(state)
+   }
+
+   fn decide_goal(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      if state.get_my_robot().get_eta() > 0 {
+         return Ok(command::Command::Wait);
+      }
+      match self.goal {
+         GameGoals::TakeSamples => self.take_samples(state),
+         GameGoals::ResearchSamples => {
+            // TODO: try strategy of getting at least two samples with bigger health values
+            self.research_samples(state)
+         }
+         GameGoals::GatherMolecules => self.gather_molecules(state),
+         GameGoals::ProduceMedicine => self.produce_medicine(state),
+         GameGoals::DropSamples => self.drop_samples(state),
+      }
+   }
+
+   fn take_samples(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      let my_robot = state.get_my_robot();
+      // TODO: take perspective samples from cloud
+      if my_robot.has_maximum_samples() {
+         return self.switch_goal(GameGoals::ResearchSamples, state);
+      }
+      if my_robot.get_location() != &module::Module::Sample {
+         return Ok(command::Command::Goto(module::Module::Sample));
+      }
+      let best_sample = my_robot.pick_sample_based_on_expertise();
+      Ok(command::Command::Connect(connect_options::ConnectOptions::SampleRank(best_sample)))
+   }
+
+   fn research_samples(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      let my_robot = state.get_my_robot();
+      let sample: &sample::Sample;
+      if let Some(found_sample) = my_robot.get_unresearched_sample() {
+         sample = found_sample;
+      } else {
+         return self.switch_goal(GameGoals::DropSamples, state);
+      }
+      if my_robot.get_location() != &module::Module::Diagnosis {
+         return Ok(command::Command::Goto(module::Module::Diagnosis));
+      }
+      Ok(command::Command::Connect(connect_options::ConnectOptions::SampleId(
+         sample.get_id(),
+      )))
+   }
+
+   fn gather_molecules(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      let my_robot = state.get_my_robot();
+      let available = state.get_available();
+      if !my_robot.can_produce_one_held_sample(available) {
+         if my_robot.has_maximum_samples() {
+            return self.switch_goal(GameGoals::DropSamples, state);
+         }
+         return self.switch_goal(GameGoals::TakeSamples, state);
+      }
+      if my_robot.has_maximum_molecules() || my_robot.has_enough_molecules() {
+         if let Some(spare_molecule) = self.pick_speculative_molecule(state) {
+            return Ok(command::Command::Connect(connect_options::ConnectOptions::MoleculeType(spare_molecule)));
+         }
+         return self.switch_goal(GameGoals::ProduceMedicine, state);
+      }
+      if my_robot.get_location() != &module::Module::Molecule {
+         return Ok(command::Command::Goto(module::Module::Molecule));
+      }
+      // TODO: blind to a CONNECT that keeps failing. When both robots ask for
+      // the last molecule of a type neither gets it, and the same CONNECT goes
+      // out every turn until the game ends (seed 2 against greedy_rank_one,
+      // from turn 61). `turn_diff::TurnDiff::my_command_failed` sees it, but
+      // the strategy is never told.
+      let policy = pickup_policy::PickupPolicy::for_robots(my_robot, state.get_enemy_robot(), available);
+      if let Some(next_molecule) = my_robot.pick_best_molecule(available, &policy) {
+         Ok(command::Command::Connect(connect_options::ConnectOptions::MoleculeType(next_molecule)))
+      } else if let Some(spare_molecule) = self.pick_speculative_molecule(state) {
+         Ok(command::Command::Connect(connect_options::ConnectOptions::MoleculeType(spare_molecule)))
+      } else {
+         self.switch_goal(GameGoals::ProduceMedicine, state)
+      }
+   }
+
+   // only while already standing at MOLECULES, never worth a trip on its own
+   fn pick_speculative_molecule(&self, state: &history::TurnState) -> Option<molecules::Molecule> {
+      let my_robot = state.get_my_robot();
+      if !self.speculative_gathering || my_robot.get_location() != &module::Module::Molecule {
+         return None;
+      }
+      let available = state.get_available();
+      let policy = pickup_policy::PickupPolicy::for_robots(my_robot, state.get_enemy_robot(), available);
+      let planned = my_robot.pick_cloud_samples(state.get_cloud(), available);
+      my_robot.pick_speculative_molecule(available, &policy, &planned)
+   }
+
+   fn produce_medicine(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      let my_robot = state.get_my_robot();
+      let sample: &sample::Sample;
+      if let Some(found_sample) = my_robot.get_most_interesting_ready_sample() {
+         sample = found_sample;
+      } else if !my_robot.get_held_samples().is_empty() {
+         return self.switch_goal(GameGoals::GatherMolecules, state);
+      } else {
+         return self.switch_goal(GameGoals::TakeSamples, state);
+      }
+      if my_robot.get_location() != &module::Module::Laboratory {
+         return Ok(command::Command::Goto(module::Module::Laboratory));
+      }
+
+      Ok(command::Command::Connect(connect_options::ConnectOptions::SampleId(
+         sample.get_id(),
+      )))
+   }
+
+   fn drop_samples(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      let my_robot = state.get_my_robot();
+      let samples_to_drop: Vec<&sample::Sample> =
+         my_robot.get_impossible_samples(state.get_available());
+      if samples_to_drop.is_empty() {
+         if my_robot.has_enough_samples() {
+            return self.switch_goal(GameGoals::GatherMolecules, state);
+         }
+         return self.switch_goal(GameGoals::TakeSamples, state);
+      }
+      if my_robot.get_location() != &module::Module::Diagnosis {
+         return Ok(command::Command::Goto(module::Module::Diagnosis));
+      }
+
+      Ok(command::Command::Connect(connect_options::ConnectOptions::SampleId(
+         samples_to_drop[0].get_id(),
+      )))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::history::{History, TurnState};
+   use super::super::module::Module;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::super::strategy::{Strategy, TurnContext};
+   use super::super::tables::LookupTables;
+   use super::super::time_budget::TimeBudget;
+   use super::{GameGoals, GoalMachineStrategy};
+   use std::time::Instant;
+
+   fn decide(strategy: &mut GoalMachineStrategy, me: RobotBuilder, available: &str) -> String {
+      let state = TurnState::new(1, me.build(), RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules(available));
+      let (history, tables) = (History::new(), LookupTables::new(Vec::new()));
+      let budget = TimeBudget::for_turn(Instant::now(), 1);
+      strategy.decide(&TurnContext::new(&state, &history, &tables, &budget)).to_string()
+   }
+
+   #[test]
+   fn annotates_the_goal() {
+      let mut strategy = GoalMachineStrategy::new();
+      strategy.goal = GameGoals::GatherMolecules;
+      let me = RobotBuilder::at(Module::Molecule)
+         .inventory("A1")
+         .sample(SampleBuilder::new(12).health(10).cost("A1 B2 E1"));
+      assert_eq!(decide(&mut strategy, me, "A5 B5 C5 D5 E5"), "CONNECT B GATHER s12 need B2 E1");
+      assert_eq!(strategy.get_goal(), Some("GatherMolecules"));
+   }
+
+   #[test]
+   fn resumes_a_recorded_goal() {
+      let mut strategy = GoalMachineStrategy::new();
+      let me = || RobotBuilder::at(Module::Molecule).sample(SampleBuilder::new(12).health(10).cost("B2"));
+      assert_eq!(decide(&mut strategy, me(), "A5 B5 C5 D5 E5"), "GOTO SAMPLES TAKE r1");
+
+      let mut strategy = GoalMachineStrategy::new();
+      strategy.set_goal("GatherMolecules").unwrap();
+      assert_eq!(decide(&mut strategy, me(), "A5 B5 C5 D5 E5"), "CONNECT B GATHER s12 need B2");
+      assert_eq!(strategy.set_goal("Dance"), Err("Unknown goal"));
+      assert_eq!(strategy.get_goal(), Some("GatherMolecules"));
+   }
+
+   #[test]
+   fn endless_goal_switching_is_an_error() {
+      let mut strategy = GoalMachineStrategy::new();
+      let state = TurnState::new(1, RobotBuilder::at(Module::Spawn).build(), RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules(""));
+      strategy.switches = GoalMachineStrategy::MAX_GOAL_SWITCHES;
+      assert!(strategy.switch_goal(GameGoals::TakeSamples, &state).is_err());
+
+      // a fresh decision starts counting again
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Spawn), ""), "GOTO SAMPLES TAKE r1");
+      assert_eq!(strategy.get_error(), None);
+   }
+
+   #[test]
+   fn walks_the_production_loop() {
+      let mut strategy = GoalMachineStrategy::new();
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Spawn), ""), "GOTO SAMPLES TAKE r1");
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Sample).eta(2), ""), "WAIT TAKE r1");
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Sample), ""), "CONNECT 1 TAKE r1");
+
+      let full_hands = RobotBuilder::at(Module::Sample)
+         .sample(SampleBuilder::new(1))
+         .sample(SampleBuilder::new(2))
+         .sample(SampleBuilder::new(3));
+      assert_eq!(decide(&mut strategy, full_hands, ""), "GOTO DIAGNOSIS DIAGNOSE s1");
+   }
+
+   #[test]
+   fn speculative_variant_fills_spare_capacity() {
+      let me = || {
+         RobotBuilder::at(Module::Molecule)
+            .inventory("A1")
+            .sample(SampleBuilder::new(12).health(10).cost("A1"))
+      };
+      let mut plain = GoalMachineStrategy::new();
+      plain.goal = GameGoals::GatherMolecules;
+      assert_eq!(decide(&mut plain, me(), "A5 B5 C5 D5 E5"), "GOTO LABORATORY PRODUCE s12");
+
+      let mut speculative = GoalMachineStrategy::new().with_speculative_gathering();
+      speculative.goal = GameGoals::GatherMolecules;
+      assert_eq!(decide(&mut speculative, me(), "A5 B5 C5 D5 E5"), "CONNECT A GATHER");
+   }
+}
@@ -0,0 +1,259 @@
+/*!
+ * Golden positions: recorded positions with the commands we accept there,
+ * so that a change of decision shows up as a failing test.
+ *
+ * ```text
+ * # a comment
+ * 14; MOLECULES 0 0 - -; DIAGNOSIS 0 0 - -; A5B5C5D5E5; A3C3D3; 0:m:1:1:B1D1E1:B => CONNECT B | CONNECT D
+ * ```
+ *
+ * One position per line in the notation of `notation`, then `=>` and the
+ * acceptable commands separated by `|`, messages left out. Nothing after
+ * `=>` means the position has not been blessed yet. Blessing keeps every
+ * set the default strategy still satisfies and replaces the others with
+ * its current decision.
+ *
+ * The default strategy carries a goal from turn to turn, so a position
+ * taken from a game starts with the goal the bot had there, as returned by
+ * `Strategy::get_goal`:
+ *
+ * ```text
+ * [GatherMolecules] 14; MOLECULES 0 0 - -; DIAGNOSIS 0 0 - -; A5B5C5D5E5; A3C3D3; 0:m:1:1:B1D1E1:B => CONNECT B
+ * ```
+ *
+ * Without it the position is decided by a fresh bot.
+ */
+
+use super::command;
+use super::memory;
+use super::notation;
+use super::strategy;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+// set to anything to rewrite the golden files instead of checking them
+pub const BLESS_VARIABLE: &str = "CODE4LIFE_BLESS";
+
+#[derive(Debug)]
+pub struct GoldenPosition {
+   goal: Option<String>,
+   position: notation::Position,
+   acceptable: Vec<command::Command>,
+}
+
+// the default strategy, working towards `goal` if there is one
+fn resume(goal: Option<&str>) -> Result<Box<dyn strategy::Strategy>, &'static str> {
+   let mut resumed = strategy::by_name(strategy::DEFAULT_STRATEGY).unwrap();
+   if let Some(goal) = goal {
+      resumed.set_goal(goal)?;
+   }
+   Ok(resumed)
+}
+
+impl GoldenPosition {
+   pub fn new(position: notation::Position, acceptable: Vec<command::Command>) -> Self {
+      Self {
+         goal: None,
+         position,
+         acceptable,
+      }
+   }
+
+   pub fn with_goal(mut self, goal: &str) -> Result<Self, &'static str> {
+      resume(Some(goal))?;
+      self.goal = Some(goal.to_owned());
+      Ok(self)
+   }
+
+   pub fn get_goal(&self) -> Option<&str> {
+      self.goal.as_deref()
+   }
+   pub fn get_position(&self) -> &notation::Position {
+      &self.position
+   }
+   pub fn get_acceptable(&self) -> &Vec<command::Command> {
+      &self.acceptable
+   }
+
+   // what the default strategy plays here from the recorded goal, without its message
+   pub fn decide(&self) -> command::Command {
+      let mut memory = memory::Memory::with_strategy(resume(self.get_goal()).unwrap());
+      memory.load_position(&self.position, Instant::now());
+      memory.process_turn().into_action()
+   }
+
+   pub fn accepts(&self, command: &command::Command) -> bool {
+      self.acceptable.iter().any(|acceptable| acceptable == command)
+   }
+}
+
+impl fmt::Display for GoldenPosition {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let acceptable = self.acceptable.iter().map(|command| command.to_string()).collect::<Vec<_>>();
+      if let Some(goal) = &self.goal {
+         write!(f, "[{}] ", goal)?;
+      }
+      write!(f, "{} => {}", self.position, acceptable.join(" | "))
+   }
+}
+
+impl FromStr for GoldenPosition {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let (goal, s) = match s.trim_start().strip_prefix('[') {
+         Some(rest) => {
+            let (goal, rest) = rest.split_once(']').ok_or("Expected ] after the goal")?;
+            (Some(goal.trim()), rest)
+         }
+         None => (None, s),
+      };
+      let (position, acceptable) = s.split_once("=>").ok_or("Expected <position> => <commands>")?;
+      let position = position.parse::<notation::Position>()?;
+      let location = position.get_state().get_my_robot().get_location();
+      let acceptable = acceptable
+         .split('|')
+         .map(str::trim)
+         .filter(|command| !command.is_empty())
+         .map(|command| command::Output::parse_at(command, location).map(command::Output::into_action))
+         .collect::<Result<Vec<_>, _>>()?;
+      let golden = Self::new(position, acceptable);
+      match goal {
+         Some(goal) => golden.with_goal(goal),
+         None => Ok(golden),
+      }
+   }
+}
+
+#[derive(Debug)]
+enum Line {
+   // comments and blank lines, written back as they were
+   Text(String),
+   Position(GoldenPosition),
+}
+
+#[derive(Debug)]
+pub struct GoldenFile {
+   lines: Vec<Line>,
+}
+
+impl GoldenFile {
+   pub fn get_positions(&self) -> impl Iterator<Item = &GoldenPosition> {
+      self.lines.iter().filter_map(|line| match line {
+         Line::Position(position) => Some(position),
+         Line::Text(_) => None,
+      })
+   }
+
+   // one line per position whose decision is not acceptable
+   pub fn check(&self) -> Vec<String> {
+      self
+         .get_positions()
+         .filter_map(|golden| {
+            let decision = golden.decide();
+            if golden.accepts(&decision) {
+               return None;
+            }
+            Some(format!("{}\n   played {}", golden, decision))
+         })
+         .collect()
+   }
+
+   // returns how many positions changed
+   pub fn bless(&mut self) -> usize {
+      let mut changed = 0;
+      for line in self.lines.iter_mut() {
+         if let Line::Position(golden) = line {
+            let decision = golden.decide();
+            if !golden.accepts(&decision) {
+               golden.acceptable = vec![decision];
+               changed += 1;
+            }
+         }
+      }
+      changed
+   }
+}
+
+impl fmt::Display for GoldenFile {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      for line in &self.lines {
+         match line {
+            Line::Text(text) => writeln!(f, "{}", text)?,
+            Line::Position(golden) => writeln!(f, "{}", golden)?,
+         }
+      }
+      Ok(())
+   }
+}
+
+impl FromStr for GoldenFile {
+   type Err = String;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let mut lines = Vec::new();
+      for (number, text) in s.lines().enumerate() {
+         let trimmed = text.trim();
+         if trimmed.is_empty() || trimmed.starts_with('#') {
+            lines.push(Line::Text(text.to_owned()));
+         } else {
+            let golden = trimmed.parse().map_err(|error| format!("line {}: {}", number + 1, error))?;
+            lines.push(Line::Position(golden));
+         }
+      }
+      Ok(Self { lines })
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::connect_options::ConnectOptions;
+   use super::super::module::Module;
+   use super::super::molecules::Molecule;
+   use super::{GoldenFile, GoldenPosition};
+
+   const FILE: &str = "# start of the game\n\
+      \n\
+      0; SAMPLES 0 0 - -; SAMPLES 0 0 - -; A5B5C5D5E5; A3B3C3; - => CONNECT 1 | CONNECT 2\n\
+      0; SAMPLES 0 0 - -; SAMPLES 0 0 - -; A5B5C5D5E5; A3B3C3; - => GOTO LABORATORY\n\
+      0; SAMPLES 0 0 - -; SAMPLES 0 0 - -; A5B5C5D5E5; A3B3C3; - =>\n";
+
+   #[test]
+   fn checks_and_blesses_positions() {
+      let mut file = FILE.parse::<GoldenFile>().unwrap();
+      assert_eq!(file.get_positions().count(), 3);
+      assert_eq!(file.to_string(), FILE.replace("=>\n", "=> \n"));
+      let decision = file.get_positions().next().unwrap().decide();
+      assert_ne!(decision, Command::Goto(Module::Laboratory));
+
+      assert_eq!(file.check().len(), 2);
+      assert_eq!(file.bless(), 2);
+      assert!(file.check().is_empty());
+      let blessed = file.to_string();
+      assert!(blessed.starts_with("# start of the game\n\n"));
+      assert!(blessed.contains("=> CONNECT 1 | CONNECT 2\n"));
+      assert_eq!(blessed.matches(&format!("=> {}\n", decision)).count(), 2);
+   }
+
+   #[test]
+   fn decides_from_the_recorded_goal() {
+      let position = "1; MOLECULES 0 0 - -; SAMPLES 0 0 - -; A5B5C5D5E5; A3B3C3; 0:m:1:1:B2:A =>";
+      let fresh = position.parse::<GoldenPosition>().unwrap();
+      let resumed = format!("[GatherMolecules] {}", position).parse::<GoldenPosition>().unwrap();
+      assert_eq!(fresh.decide(), Command::Goto(Module::Sample));
+      assert_eq!(resumed.decide(), Command::Connect(ConnectOptions::MoleculeType(Molecule::B)));
+      assert_eq!(resumed.to_string(), format!("[GatherMolecules] {} ", position));
+   }
+
+   #[test]
+   fn reports_the_faulty_line() {
+      let error = "# comment\n0; nowhere => WAIT\n".parse::<GoldenFile>().unwrap_err();
+      assert!(error.starts_with("line 2:"));
+      let error = "# comment\n[Dance] 0; SAMPLES 0 0 - -; SAMPLES 0 0 - -; A5B5C5D5E5; A3B3C3; - =>\n"
+         .parse::<GoldenFile>()
+         .unwrap_err();
+      assert_eq!(error, "line 2: Unknown goal");
+   }
+}
@@ -0,0 +1,306 @@
+use super::carried_by;
+use super::molecules;
+use super::robot;
+use super::sample;
+use std::collections::HashMap;
+
+/**
+ * Everything the referee told us in one turn.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct TurnState {
+   turn: u16,
+   my_robot: robot::Robot,
+   enemy_robot: robot::Robot,
+   cloud: Vec<sample::Sample>,
+   available: molecules::Molecules,
+}
+
+impl TurnState {
+   pub fn new(
+      turn: u16,
+      my_robot: robot::Robot,
+      enemy_robot: robot::Robot,
+      cloud: Vec<sample::Sample>,
+      available: molecules::Molecules,
+   ) -> Self {
+      Self {
+         turn,
+         my_robot,
+         enemy_robot,
+         cloud,
+         available,
+      }
+   }
+   pub fn get_turn(&self) -> u16 {
+      self.turn
+   }
+   pub fn get_my_robot(&self) -> &robot::Robot {
+      &self.my_robot
+   }
+   pub fn get_enemy_robot(&self) -> &robot::Robot {
+      &self.enemy_robot
+   }
+   pub fn get_robot(&self, owner: carried_by::CarriedBy) -> Option<&robot::Robot> {
+      match owner {
+         carried_by::CarriedBy::Me => Some(&self.my_robot),
+         carried_by::CarriedBy::Other => Some(&self.enemy_robot),
+         carried_by::CarriedBy::Cloud => None,
+      }
+   }
+   pub fn get_cloud(&self) -> &Vec<sample::Sample> {
+      &self.cloud
+   }
+   pub fn get_available(&self) -> &molecules::Molecules {
+      &self.available
+   }
+
+   // who holds the sample and the sample itself, if it is still in play
+   pub fn find_sample(&self, id: u8) -> Option<(carried_by::CarriedBy, &sample::Sample)> {
+      let holders = [
+         (carried_by::CarriedBy::Me, self.my_robot.get_held_samples()),
+         (carried_by::CarriedBy::Other, self.enemy_robot.get_held_samples()),
+         (carried_by::CarriedBy::Cloud, &self.cloud),
+      ];
+      for (owner, samples) in holders {
+         if let Some(found_sample) = samples.iter().find(|sample| sample.get_id() == id) {
+            return Some((owner, found_sample));
+         }
+      }
+      None
+   }
+
+   pub fn all_samples(&self) -> impl Iterator<Item = &sample::Sample> {
+      self.my_robot
+         .get_held_samples()
+         .iter()
+         .chain(self.enemy_robot.get_held_samples().iter())
+         .chain(self.cloud.iter())
+   }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleEvent {
+   Drawn(carried_by::CarriedBy),
+   Diagnosed(carried_by::CarriedBy),
+   Dropped(carried_by::CarriedBy),
+   TakenFromCloud(carried_by::CarriedBy),
+   Produced(carried_by::CarriedBy),
+}
+
+/**
+ * Previous turns and what happened to every sample id along the way.
+ */
+#[derive(Debug, Default)]
+pub struct History {
+   states: Vec<TurnState>,
+   sample_events: HashMap<u8, Vec<(u16, SampleEvent)>>,
+   enemy_last_moved: Option<u16>,
+}
+
+impl History {
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   pub fn record(&mut self, state: TurnState) {
+      if let Some(previous) = self.states.last() {
+         let events = Self::sample_events_between(previous, &state);
+         for (id, event) in events {
+            self.sample_events.entry(id).or_default().push((state.turn, event));
+         }
+         let previous_enemy = previous.get_enemy_robot();
+         let enemy = state.get_enemy_robot();
+         if previous_enemy.get_location() != enemy.get_location() || enemy.get_eta() > 0 {
+            self.enemy_last_moved = Some(state.turn);
+         }
+      } else {
+         for (owner, robot) in [
+            (carried_by::CarriedBy::Me, state.get_my_robot()),
+            (carried_by::CarriedBy::Other, state.get_enemy_robot()),
+         ] {
+            for sample in robot.get_held_samples() {
+               self.sample_events
+                  .entry(sample.get_id())
+                  .or_default()
+                  .push((state.turn, SampleEvent::Drawn(owner)));
+            }
+         }
+      }
+      self.states.push(state);
+   }
+
+   pub fn sample_events_between(previous: &TurnState, current: &TurnState) -> Vec<(u8, SampleEvent)> {
+      let mut events = Vec::new();
+      for sample in current.all_samples() {
+         let (owner, _) = current.find_sample(sample.get_id()).unwrap();
+         match previous.find_sample(sample.get_id()) {
+            None => events.push((sample.get_id(), SampleEvent::Drawn(owner))),
+            Some((carried_by::CarriedBy::Cloud, _)) if owner != carried_by::CarriedBy::Cloud => {
+               events.push((sample.get_id(), SampleEvent::TakenFromCloud(owner)))
+            }
+            Some((previous_owner, _)) if owner == carried_by::CarriedBy::Cloud && previous_owner != owner => {
+               events.push((sample.get_id(), SampleEvent::Dropped(previous_owner)))
+            }
+            Some((previous_owner, previous_sample)) => {
+               let was_unresearched = matches!(previous_sample.get_health(), sample::SampleHealth::Unresearched);
+               let is_researched = matches!(sample.get_health(), sample::SampleHealth::Researched(_));
+               if previous_owner == owner && was_unresearched && is_researched {
+                  events.push((sample.get_id(), SampleEvent::Diagnosed(owner)));
+               }
+            }
+         }
+      }
+      for sample in previous.all_samples() {
+         if current.find_sample(sample.get_id()).is_none() {
+            let (previous_owner, _) = previous.find_sample(sample.get_id()).unwrap();
+            events.push((sample.get_id(), SampleEvent::Produced(previous_owner)));
+         }
+      }
+      events
+   }
+
+   pub fn get_states(&self) -> &Vec<TurnState> {
+      &self.states
+   }
+
+   pub fn get_last(&self) -> Option<&TurnState> {
+      self.states.last()
+   }
+
+   // the turn before the most recent one
+   pub fn get_previous(&self) -> Option<&TurnState> {
+      self.states.iter().rev().nth(1)
+   }
+
+   pub fn get_sample_events(&self, id: u8) -> &[(u16, SampleEvent)] {
+      self.sample_events.get(&id).map(|events| events.as_slice()).unwrap_or(&[])
+   }
+
+   // ids of samples `owner` dropped into the cloud that are still lying there
+   pub fn samples_dropped_by(&self, owner: carried_by::CarriedBy) -> Vec<u8> {
+      let mut dropped = match self.get_last() {
+         Some(state) => state
+            .get_cloud()
+            .iter()
+            .map(|sample| sample.get_id())
+            .filter(|id| {
+               self.get_sample_events(*id)
+                  .iter()
+                  .rev()
+                  .find(|(_, event)| matches!(event, SampleEvent::Dropped(_)))
+                  .is_some_and(|(_, event)| *event == SampleEvent::Dropped(owner))
+            })
+            .collect::<Vec<_>>(),
+         None => Vec::new(),
+      };
+      dropped.sort_unstable();
+      dropped
+   }
+
+   pub fn samples_produced_by(&self, owner: carried_by::CarriedBy) -> Vec<u8> {
+      let mut produced = self
+         .sample_events
+         .iter()
+         .filter(|(_, events)| events.iter().any(|(_, event)| *event == SampleEvent::Produced(owner)))
+         .map(|(id, _)| *id)
+         .collect::<Vec<_>>();
+      produced.sort_unstable();
+      produced
+   }
+
+   // None when the turns went backwards, e.g. after a pasted position
+   pub fn turns_since_enemy_moved(&self) -> Option<u16> {
+      let last = self.get_last()?;
+      self.enemy_last_moved.and_then(|turn| last.get_turn().checked_sub(turn))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::carried_by::CarriedBy;
+   use super::super::module::Module;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::{History, SampleEvent, TurnState};
+
+   fn state(turn: u16, me: RobotBuilder, enemy: RobotBuilder, cloud: Vec<SampleBuilder>) -> TurnState {
+      TurnState::new(
+         turn,
+         me.build(),
+         enemy.build(),
+         cloud.into_iter().map(|sample| sample.build()).collect(),
+         molecules("A5 B5 C5 D5 E5"),
+      )
+   }
+
+   #[test]
+   fn tracks_sample_lifecycle() {
+      let mut history = History::new();
+      history.record(state(0, RobotBuilder::at(Module::Spawn), RobotBuilder::at(Module::Spawn), vec![]));
+      history.record(state(
+         1,
+         RobotBuilder::at(Module::Sample).sample(SampleBuilder::new(1)),
+         RobotBuilder::at(Module::Sample).sample(SampleBuilder::new(2)),
+         vec![],
+      ));
+      history.record(state(
+         2,
+         RobotBuilder::at(Module::Diagnosis).sample(SampleBuilder::new(1).health(10).cost("A1")),
+         RobotBuilder::at(Module::Diagnosis),
+         vec![SampleBuilder::new(2).health(20).cost("B1")],
+      ));
+      history.record(state(
+         3,
+         RobotBuilder::at(Module::Laboratory),
+         RobotBuilder::at(Module::Diagnosis),
+         vec![SampleBuilder::new(2).health(20).cost("B1")],
+      ));
+
+      assert_eq!(
+         history.get_sample_events(1),
+         &[
+            (1, SampleEvent::Drawn(CarriedBy::Me)),
+            (2, SampleEvent::Diagnosed(CarriedBy::Me)),
+            (3, SampleEvent::Produced(CarriedBy::Me)),
+         ]
+      );
+      assert_eq!(
+         history.get_sample_events(2),
+         &[(1, SampleEvent::Drawn(CarriedBy::Other)), (2, SampleEvent::Dropped(CarriedBy::Other))]
+      );
+      assert_eq!(history.samples_dropped_by(CarriedBy::Other), vec![2]);
+      assert!(history.samples_dropped_by(CarriedBy::Me).is_empty());
+      assert_eq!(history.samples_produced_by(CarriedBy::Me), vec![1]);
+      assert_eq!(history.get_previous().unwrap().get_turn(), 2);
+   }
+
+   #[test]
+   fn picked_up_samples_leave_the_dropped_list() {
+      let mut history = History::new();
+      history.record(state(0, RobotBuilder::at(Module::Diagnosis), RobotBuilder::at(Module::Diagnosis), vec![
+         SampleBuilder::new(5).health(10),
+      ]));
+      history.record(state(
+         1,
+         RobotBuilder::at(Module::Diagnosis).sample(SampleBuilder::new(5).health(10)),
+         RobotBuilder::at(Module::Diagnosis),
+         vec![],
+      ));
+      assert_eq!(history.get_sample_events(5), &[(1, SampleEvent::TakenFromCloud(CarriedBy::Me))]);
+      assert!(history.samples_dropped_by(CarriedBy::Other).is_empty());
+   }
+
+   #[test]
+   fn counts_turns_since_enemy_moved() {
+      let mut history = History::new();
+      assert_eq!(history.turns_since_enemy_moved(), None);
+      history.record(state(0, RobotBuilder::at(Module::Spawn), RobotBuilder::at(Module::Spawn), vec![]));
+      history.record(state(1, RobotBuilder::at(Module::Spawn), RobotBuilder::at(Module::Sample).eta(1), vec![]));
+      history.record(state(2, RobotBuilder::at(Module::Spawn), RobotBuilder::at(Module::Sample), vec![]));
+      history.record(state(3, RobotBuilder::at(Module::Spawn), RobotBuilder::at(Module::Sample), vec![]));
+      assert_eq!(history.turns_since_enemy_moved(), Some(2));
+      // a pasted position may carry an earlier turn number
+      history.record(state(0, RobotBuilder::at(Module::Spawn), RobotBuilder::at(Module::Sample), vec![]));
+      assert_eq!(history.turns_since_enemy_moved(), None);
+   }
+}
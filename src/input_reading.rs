@@ -3,14 +3,25 @@ use super::molecules;
 use super::robot;
 use super::sample;
 use std::io;
+use std::io::BufRead;
+use std::time::Instant;
 
-pub fn ignore_projects() -> () {
+pub fn parse_projects() -> Vec<molecules::Molecules> {
+   parse_projects_from(&mut io::stdin().lock())
+}
+
+pub fn parse_projects_from<R: BufRead>(reader: &mut R) -> Vec<molecules::Molecules> {
    let mut input_line = String::new();
-   io::stdin().read_line(&mut input_line).unwrap();
+   reader.read_line(&mut input_line).unwrap();
    let project_count = parse_input!(input_line, u8);
+   let mut projects = Vec::new();
    for _ in 0..project_count {
-      io::stdin().read_line(&mut input_line).unwrap();
+      input_line.clear();
+      reader.read_line(&mut input_line).unwrap();
+      let inputs = input_line.split_whitespace().collect::<Vec<_>>();
+      projects.push(molecules::Molecules::from_slice(&inputs[0..5]));
    }
+   projects
 }
 
 pub fn parse_turn_input() -> (
@@ -18,30 +29,42 @@ pub fn parse_turn_input() -> (
    robot::Robot,
    Vec<sample::Sample>,
    molecules::Molecules,
+   Instant,
+) {
+   parse_turn_input_from(&mut io::stdin().lock())
+}
+
+pub fn parse_turn_input_from<R: BufRead>(reader: &mut R) -> (
+   robot::Robot,
+   robot::Robot,
+   Vec<sample::Sample>,
+   molecules::Molecules,
+   Instant,
 ) {
    let mut input_line = String::new();
-   io::stdin().read_line(&mut input_line).unwrap();
+   reader.read_line(&mut input_line).unwrap();
+   let turn_started = Instant::now();
    let mut my_robot =
       robot::Robot::new_from_inputs(input_line.split_whitespace().collect::<Vec<_>>());
 
    input_line.clear();
-   io::stdin().read_line(&mut input_line).unwrap();
+   reader.read_line(&mut input_line).unwrap();
    let mut enemy_robot =
       robot::Robot::new_from_inputs(input_line.split_whitespace().collect::<Vec<_>>());
 
    input_line.clear();
-   io::stdin().read_line(&mut input_line).unwrap();
+   reader.read_line(&mut input_line).unwrap();
    let inputs = input_line.split_whitespace().collect::<Vec<_>>();
    let available = molecules::Molecules::from_slice(&inputs[0..5]);
 
    let mut cloud = Vec::new();
 
    input_line.clear();
-   io::stdin().read_line(&mut input_line).unwrap();
+   reader.read_line(&mut input_line).unwrap();
    let sample_count = parse_input!(input_line, u16);
    for _ in 0..sample_count {
       input_line.clear();
-      io::stdin().read_line(&mut input_line).unwrap();
+      reader.read_line(&mut input_line).unwrap();
       let inputs = input_line.split_whitespace().collect::<Vec<_>>();
 
       let sample_id = parse_input!(inputs[0], u8);
@@ -61,5 +84,5 @@ pub fn parse_turn_input() -> (
          carried_by::CarriedBy::Cloud => cloud.push(sample),
       }
    }
-   return (my_robot, enemy_robot, cloud, available);
+   (my_robot, enemy_robot, cloud, available, turn_started)
 }
@@ -0,0 +1,106 @@
+/*!
+ * Just enough JSON to write records, so exports need no dependency.
+ */
+
+use std::fmt;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+   Null,
+   Bool(bool),
+   Number(i64),
+   String(String),
+   Array(Vec<Json>),
+   // keys keep their insertion order
+   Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+   pub fn string(text: &str) -> Json {
+      Json::String(text.to_owned())
+   }
+
+   pub fn numbers<T: Copy + Into<i64>>(values: &[T]) -> Json {
+      Json::Array(values.iter().map(|value| Json::Number((*value).into())).collect())
+   }
+
+   pub fn optional(value: Option<Json>) -> Json {
+      value.unwrap_or(Json::Null)
+   }
+}
+
+fn write_string(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+   f.write_char('"')?;
+   for c in text.chars() {
+      match c {
+         '"' => f.write_str("\\\"")?,
+         '\\' => f.write_str("\\\\")?,
+         '\n' => f.write_str("\\n")?,
+         '\r' => f.write_str("\\r")?,
+         '\t' => f.write_str("\\t")?,
+         c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+         c => f.write_char(c)?,
+      }
+   }
+   f.write_char('"')
+}
+
+// compact, on a single line
+impl fmt::Display for Json {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self {
+         Json::Null => write!(f, "null"),
+         Json::Bool(value) => write!(f, "{}", value),
+         Json::Number(value) => write!(f, "{}", value),
+         Json::String(text) => write_string(f, text),
+         Json::Array(values) => {
+            f.write_char('[')?;
+            for (index, value) in values.iter().enumerate() {
+               if index > 0 {
+                  f.write_char(',')?;
+               }
+               write!(f, "{}", value)?;
+            }
+            f.write_char(']')
+         }
+         Json::Object(fields) => {
+            f.write_char('{')?;
+            for (index, (key, value)) in fields.iter().enumerate() {
+               if index > 0 {
+                  f.write_char(',')?;
+               }
+               write_string(f, key)?;
+               write!(f, ":{}", value)?;
+            }
+            f.write_char('}')
+         }
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::Json;
+
+   #[test]
+   fn serializes_nested_values() {
+      let value = Json::Object(vec![
+         ("turn", Json::Number(-3)),
+         ("done", Json::Bool(false)),
+         ("counts", Json::numbers(&[1i8, 0, 2])),
+         ("goal", Json::optional(None)),
+         ("empty", Json::Array(vec![])),
+         ("nested", Json::Object(vec![("name", Json::string("GOTO"))])),
+      ]);
+      assert_eq!(
+         value.to_string(),
+         r#"{"turn":-3,"done":false,"counts":[1,0,2],"goal":null,"empty":[],"nested":{"name":"GOTO"}}"#
+      );
+   }
+
+   #[test]
+   fn escapes_strings() {
+      assert_eq!(Json::string("say \"hi\"\\\n\u{1}é").to_string(), r#""say \"hi\"\\\n\u0001é""#);
+   }
+}
@@ -0,0 +1,54 @@
+/*!
+ * Bring data on patient samples from the diagnosis machine to the laboratory
+ * with enough molecules to produce medicine!
+ *
+ * 1. Collect sample data at the SAMPLES module
+ * 2. Analyze them at the DIAGNOSIS module
+ * 3. Gather required molecules for the medicines at the MOLECULES module
+ * 4. Produce the medicines at the LABORATORY modue
+ *
+ * can carry up to 3 sample data files and 10 molecules
+ * molecule types = A,B,C,D,E
+ */
+
+macro_rules! parse_input {
+   ($x:expr, $t:ty) => {
+      $x.trim().parse::<$t>().unwrap()
+   };
+}
+
+pub mod input_reading;
+pub mod module;
+pub mod carried_by;
+pub mod sample;
+pub mod connect_options;
+pub mod command;
+pub mod molecules;
+pub mod pickup_policy;
+pub mod robot;
+pub mod strategy;
+pub mod goal_machine;
+pub mod opponents;
+pub mod memory;
+pub mod history;
+#[cfg(test)]
+pub mod scenario;
+pub mod tables;
+pub mod time_budget;
+pub mod turn_diff;
+pub mod validator;
+pub mod random;
+pub mod zobrist;
+pub mod transposition;
+pub mod game;
+pub mod replay;
+pub mod notation;
+pub mod simulation;
+pub mod external;
+pub mod golden;
+pub mod fuzz;
+pub mod analytics;
+#[cfg(feature = "export")]
+pub mod json;
+#[cfg(feature = "export")]
+pub mod export;
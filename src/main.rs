@@ -1,41 +1,33 @@
-/**
- * Bring data on patient samples from the diagnosis machine to the laboratory
- * with enough molecules to produce medicine!
- **/
-
-/**
- * 1. Collect sample data at the SAMPLES module
- * 2. Analyze them at the DIAGNOSIS module
- * 3. Gather required molecules for the medicines at the MOLECULES module
- * 4. Produce the medicines at the LABORATORY modue
- *
- * can carry up to 3 sample data files and 10 molecules
- * molecule types = A,B,C,D,E
- */
-
-macro_rules! parse_input {
-   ($x:expr, $t:ty) => {
-      $x.trim().parse::<$t>().unwrap()
-   };
-}
-
-mod input_reading;
-mod module;
-mod carried_by;
-mod sample;
-mod connect_options;
-mod command;
-mod molecules;
-mod robot;
-mod memory;
-
+use code_4_life::memory;
+use code_4_life::strategy;
+use std::env;
+use std::process;
 
 fn main() {
-   let mut state_machine = memory::Memory::new();
-   state_machine.ignore_initial_input();
+   let name = env::args()
+      .nth(1)
+      .or_else(|| env::var(strategy::STRATEGY_VARIABLE).ok())
+      .unwrap_or_else(|| strategy::DEFAULT_STRATEGY.to_owned());
+   let chosen = match strategy::by_name(&name) {
+      Some(chosen) => chosen,
+      None => {
+         eprintln!("unknown strategy {}, expected one of {}", name, strategy::names().join(", "));
+         process::exit(1);
+      }
+   };
+   let mut state_machine = memory::Memory::with_strategy(chosen);
+   #[cfg(feature = "export")]
+   if let Ok(path) = env::var(code_4_life::export::EXPORT_VARIABLE) {
+      match code_4_life::export::Exporter::to_file(&path) {
+         Ok(exporter) => state_machine.set_exporter(exporter),
+         Err(error) => eprintln!("cannot export to {}: {}", path, error),
+      }
+   }
+   state_machine.parse_initial_input();
    loop {
       state_machine.parse_turn_input();
       // eprintln!("{:?}", state_machine);
-      println!("{}", state_machine.process_turn().to_string());
+      let output = state_machine.process_turn();
+      println!("{}", state_machine.finish_turn(output));
    }
 }
@@ -1,166 +1,310 @@
 use super::command;
 use super::connect_options;
+#[cfg(feature = "export")]
+use super::export;
+use super::history;
 use super::input_reading;
 use super::module;
 use super::molecules;
-use super::robot;
-use super::sample;
+use super::notation;
+use super::strategy;
+use super::tables;
+use super::time_budget;
+use super::turn_diff;
+use super::validator;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
 
 #[derive(Debug)]
-enum GameGoals {
-   TakeSamples,
-   ResearchSamples,
-   GatherMolecules,
-   ProduceMedicine,
-   DropSamples,
+pub struct Memory {
+   strategy: Box<dyn strategy::Strategy>,
+   state: history::TurnState,
+   turn: u16,
+   history: history::History,
+   last_command: Option<command::Command>,
+   last_diff: Option<turn_diff::TurnDiff>,
+   tables: tables::LookupTables,
+   time_budget: time_budget::TimeBudget,
+   // when the initial input started, the first turn's second runs from there
+   game_started: Option<Instant>,
+   #[cfg(feature = "export")]
+   exporter: Option<export::Exporter>,
 }
 
-#[derive(Debug)]
-pub struct Memory {
-   goal: GameGoals,
-   my_robot: robot::Robot,
-   enemy_robot: robot::Robot,
-   available: molecules::Molecules,
-   cloud: Vec<sample::Sample>,
+impl Default for Memory {
+   fn default() -> Self {
+      Self::new()
+   }
 }
 
 impl Memory {
    pub fn new() -> Self {
+      Self::with_strategy(strategy::by_name(strategy::DEFAULT_STRATEGY).unwrap())
+   }
+
+   pub fn with_strategy(strategy: Box<dyn strategy::Strategy>) -> Self {
       Self {
-         goal: GameGoals::TakeSamples,
-         my_robot: robot::Robot::new(),
-         enemy_robot: robot::Robot::new(),
-         available: molecules::Molecules::new(),
-         cloud: Vec::new(),
+         strategy,
+         state: history::TurnState::default(),
+         turn: 0,
+         history: history::History::new(),
+         last_command: None,
+         last_diff: None,
+         tables: tables::LookupTables::new(Vec::new()),
+         time_budget: time_budget::TimeBudget::for_turn(Instant::now(), 0),
+         game_started: None,
+         #[cfg(feature = "export")]
+         exporter: None,
       }
    }
 
-   pub fn ignore_initial_input(&self) {
-      input_reading::ignore_projects();
+   pub fn parse_initial_input(&mut self) {
+      self.parse_initial_input_from(&mut io::stdin().lock());
+   }
+
+   // reading the projects and building the tables count against the first turn
+   pub fn parse_initial_input_from<R: BufRead>(&mut self, input: &mut R) {
+      self.game_started = Some(Instant::now());
+      self.set_projects(input_reading::parse_projects_from(input));
+   }
+
+   pub fn set_projects(&mut self, projects: Vec<molecules::Molecules>) {
+      self.tables = tables::LookupTables::new(projects);
+   }
+
+   // every decision from now on is written out, see `export`
+   #[cfg(feature = "export")]
+   pub fn set_exporter(&mut self, exporter: export::Exporter) {
+      self.exporter = Some(exporter);
+   }
+
+   pub fn get_tables(&self) -> &tables::LookupTables {
+      &self.tables
    }
 
    pub fn parse_turn_input(&mut self) {
-      let (my_robot, enemy_robot, cloud, available) = input_reading::parse_turn_input();
-      self.my_robot = my_robot;
-      self.enemy_robot = enemy_robot;
-      self.cloud = cloud;
-      self.available = available;
+      let (my_robot, enemy_robot, cloud, available, turn_started) = input_reading::parse_turn_input();
+      let state = history::TurnState::new(self.turn, my_robot, enemy_robot, cloud, available);
+      self.observe(state, turn_started);
    }
 
-   pub fn process_turn(&mut self) -> command::Command {
-      if self.my_robot.get_eta() > 0 {
-         return command::Command::Wait;
-      }
-      match self.goal {
-         GameGoals::TakeSamples => {
-            return self.take_samples();
-         }
-         GameGoals::ResearchSamples => {
-            // TODO: try strategy of getting at least two samples with bigger health values
-            return self.research_samples();
+   // takes in a parsed turn, whether it came from stdin, a simulation or a fixture
+   pub fn observe(&mut self, state: history::TurnState, turn_started: Instant) {
+      let turn_started = match self.game_started {
+         Some(game_started) if self.turn == 0 => game_started,
+         _ => turn_started,
+      };
+      self.time_budget = time_budget::TimeBudget::for_turn(turn_started, self.turn);
+      self.state = state;
+      self.history.record(self.state.clone());
+      if cfg!(debug_assertions) {
+         if let Some(current) = self.history.get_last() {
+            for violation in validator::validate(self.history.get_previous(), current) {
+               eprintln!("turn {}: {}", self.turn, violation);
+            }
          }
-         GameGoals::GatherMolecules => {
-            return self.gather_molecules();
-         }
-         GameGoals::ProduceMedicine => {
-            return self.produce_medicine();
-         }
-         GameGoals::DropSamples => {
-            return self.drop_samples();
+      }
+      if let (Some(previous), Some(current)) = (self.history.get_previous(), self.history.get_last()) {
+         let diff = turn_diff::TurnDiff::new(previous, current, self.last_command.as_ref());
+         if cfg!(debug_assertions) && diff.my_command_failed() {
+            eprintln!(
+               "command {:?} had no effect, looked like {:?}",
+               self.last_command,
+               diff.get_my_command()
+            );
          }
+         self.last_diff = Some(diff);
       }
+      self.turn += 1;
    }
 
-   fn take_samples(&mut self) -> command::Command {
-      // TODO: take perspective samples from cloud
-      if self.my_robot.has_maximum_samples() {
-         self.goal = GameGoals::ResearchSamples;
-         return self.process_turn();
-      }
-      if self.my_robot.get_location() != &module::Module::Sample {
-         return command::Command::Goto(module::Module::Sample);
-      }
-      let best_sample = self.my_robot.pick_sample_based_on_expertise();
-      return command::Command::Connect(connect_options::ConnectOptions::SampleRank(best_sample));
+   // picks up a pasted position as if the referee had just sent it
+   pub fn load_position(&mut self, position: &notation::Position, turn_started: Instant) {
+      self.set_projects(position.get_projects().clone());
+      self.observe(position.get_state().clone(), turn_started);
+   }
+
+   // what both robots apparently did between the last two turns
+   pub fn get_last_diff(&self) -> Option<&turn_diff::TurnDiff> {
+      self.last_diff.as_ref()
+   }
+
+   pub fn get_history(&self) -> &history::History {
+      &self.history
    }
 
-   fn research_samples(&mut self) -> command::Command {
-      let sample: &sample::Sample;
-      if let Some(found_sample) = self.my_robot.get_unresearched_sample() {
-         sample = found_sample;
-      } else {
-         self.goal = GameGoals::DropSamples;
-         return self.process_turn();
+   pub fn get_time_budget(&self) -> &time_budget::TimeBudget {
+      &self.time_budget
+   }
+
+   pub fn get_state(&self) -> &history::TurnState {
+      &self.state
+   }
+
+   pub fn get_strategy(&self) -> &dyn strategy::Strategy {
+      self.strategy.as_ref()
+   }
+
+   // what the strategy decides, before `finish_turn` checks it
+   pub fn process_turn(&mut self) -> command::Output {
+      let context = strategy::TurnContext::new(&self.state, &self.history, &self.tables, &self.time_budget);
+      let output = self.strategy.decide(&context);
+      if let Some(error) = self.strategy.get_error() {
+         eprintln!("turn {}: {} failed: {}", self.turn, self.strategy.name(), error);
       }
-      if self.my_robot.get_location() != &module::Module::Diagnosis {
-         return command::Command::Goto(module::Module::Diagnosis);
+      self.last_command = Some(output.get_action().clone());
+      output
+   }
+
+   /**
+    * The line to print for `output`: made legal, then exported, so the export
+    * records what the referee actually receives.
+    */
+   pub fn finish_turn(&mut self, output: command::Output) -> command::Output {
+      let output = self.ensure_legal(output);
+      #[cfg(feature = "export")]
+      if let Some(exporter) = self.exporter.as_mut() {
+         exporter.write(&export::turn_json(
+            &self.state,
+            self.tables.get_projects(),
+            self.strategy.name(),
+            self.strategy.get_goal(),
+            &output,
+            self.time_budget.elapsed(),
+         ));
       }
-      return command::Command::Connect(connect_options::ConnectOptions::SampleId(
-         sample.get_id(),
-      ));
+      output
    }
 
-   fn gather_molecules(&mut self) -> command::Command {
-      if !self.my_robot.can_produce_one_held_sample(&self.available) {
-         if self.my_robot.has_maximum_samples() {
-            self.goal = GameGoals::DropSamples;
-         } else {
-            self.goal = GameGoals::TakeSamples;
+   /**
+    * Safety net before printing: an illegal command would waste the turn, so it
+    * is replaced with a legal fallback and the bug is logged.
+    */
+   pub fn ensure_legal(&mut self, output: command::Output) -> command::Output {
+      let state = &self.state;
+      let output = match output.get_action().check(state.get_my_robot(), state.get_available(), state.get_cloud()) {
+         Ok(()) => output,
+         Err(reason) => {
+            let fallback = self.legal_fallback().with_message("FALLBACK");
+            eprintln!("illegal command {} ({}), sending {} instead", output, reason, fallback);
+            fallback
          }
-         return self.process_turn();
-      }
-      if self.my_robot.has_maximum_molecules() || self.my_robot.has_enough_molecules() {
-         self.goal = GameGoals::ProduceMedicine;
-         return self.process_turn();
+      };
+      self.last_command = Some(output.get_action().clone());
+      output
+   }
+
+   // a command that is legal and still moves the game forward, WAIT as a last resort
+   pub fn legal_fallback(&self) -> command::Command {
+      let (my_robot, available, cloud) = (self.state.get_my_robot(), self.state.get_available(), self.state.get_cloud());
+      let mut candidates = Vec::new();
+      if let Some(sample) = my_robot.get_most_interesting_ready_sample() {
+         candidates.push(command::Command::Connect(connect_options::ConnectOptions::SampleId(
+            sample.get_id(),
+         )));
+         candidates.push(command::Command::Goto(module::Module::Laboratory));
       }
-      if self.my_robot.get_location() != &module::Module::Molecule {
-         return command::Command::Goto(module::Module::Molecule);
+      if let Some(sample) = my_robot.get_unresearched_sample() {
+         candidates.push(command::Command::Connect(connect_options::ConnectOptions::SampleId(
+            sample.get_id(),
+         )));
+         candidates.push(command::Command::Goto(module::Module::Diagnosis));
       }
-      if let Some(next_molecule) = self.my_robot.pick_best_molecule(&self.available) {
-         return command::Command::Connect(connect_options::ConnectOptions::MoleculeType(next_molecule));
-      } else {
-         self.goal = GameGoals::ProduceMedicine;
-         return self.process_turn();
+      if !my_robot.has_maximum_samples() {
+         candidates.push(command::Command::Connect(connect_options::ConnectOptions::SampleRank(
+            my_robot.pick_sample_based_on_expertise(),
+         )));
       }
+      candidates.push(command::Command::Wait);
+      candidates
+         .into_iter()
+         .find(|command| command.is_legal(my_robot, available, cloud))
+         .unwrap_or(command::Command::Wait)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::connect_options::ConnectOptions;
+   use super::super::history::TurnState;
+   use super::super::module::Module;
+   use super::super::molecules::Molecule;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::Memory;
+   use std::time::{Duration, Instant};
+
+   #[test]
+   fn ensure_legal_replaces_illegal_commands() {
+      let mut memory = Memory::new();
+      let me = RobotBuilder::at(Module::Molecule)
+         .inventory("A1")
+         .sample(SampleBuilder::new(3).health(10).cost("A1"))
+         .build();
+      let state = TurnState::new(0, me, RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules("A5 B5 C5 D5"));
+      memory.observe(state, Instant::now());
+
+      let legal = Command::Connect(ConnectOptions::MoleculeType(Molecule::A));
+      assert_eq!(memory.ensure_legal(legal.clone().into()), legal.into());
+
+      let out_of_stock = Command::Connect(ConnectOptions::MoleculeType(Molecule::E));
+      let fallback = Command::Goto(Module::Laboratory).with_message("FALLBACK");
+      assert_eq!(memory.ensure_legal(out_of_stock.into()), fallback);
+      assert_eq!(memory.last_command.as_ref(), Some(fallback.get_action()));
    }
 
-   fn produce_medicine(&mut self) -> command::Command {
-      let sample: &sample::Sample;
-      if let Some(found_sample) = self.my_robot.get_most_interesting_ready_sample() {
-         sample = found_sample;
-      } else if self.my_robot.get_held_samples().len() > 0 {
-         self.goal = GameGoals::GatherMolecules;
-         return self.process_turn();
-      } else {
-         self.goal = GameGoals::TakeSamples;
-         return self.process_turn();
-      }
-      if self.my_robot.get_location() != &module::Module::Laboratory {
-         return command::Command::Goto(module::Module::Laboratory);
-      }
+   #[test]
+   fn process_turn_asks_the_strategy() {
+      let mut memory = Memory::new();
+      let state = TurnState::new(0, RobotBuilder::at(Module::Spawn).build(), RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules(""));
+      memory.observe(state, Instant::now());
+      assert_eq!(memory.get_strategy().name(), "goal_machine");
+      assert_eq!(memory.process_turn().to_string(), "GOTO SAMPLES TAKE r1");
+      assert_eq!(memory.get_history().get_states().len(), 1);
+   }
 
-      return command::Command::Connect(connect_options::ConnectOptions::SampleId(
-         sample.get_id(),
-      ));
+   #[test]
+   fn first_turn_budget_counts_the_initial_input() {
+      let mut memory = Memory::new();
+      memory.parse_initial_input_from(&mut "1\n3 3 0 0 3\n".as_bytes());
+      std::thread::sleep(Duration::from_millis(5));
+      let state = TurnState::new(0, RobotBuilder::at(Module::Spawn).build(), RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules(""));
+      memory.observe(state, Instant::now());
+      assert_eq!(memory.get_tables().get_projects().len(), 1);
+      assert!(memory.get_time_budget().elapsed() >= Duration::from_millis(5));
    }
 
-   fn drop_samples(&mut self) -> command::Command {
-      let samples_to_drop: Vec<&sample::Sample> =
-         self.my_robot.get_impossible_samples(&self.available);
-      if samples_to_drop.len() == 0 {
-         if self.my_robot.has_enough_samples() {
-            self.goal = GameGoals::GatherMolecules;
-         } else {
-            self.goal = GameGoals::TakeSamples;
+   #[cfg(feature = "export")]
+   #[test]
+   fn exports_the_command_actually_sent() {
+      use super::super::export::Exporter;
+      use std::cell::RefCell;
+      use std::io;
+      use std::rc::Rc;
+
+      #[derive(Clone, Default)]
+      struct Shared(Rc<RefCell<Vec<u8>>>);
+      impl io::Write for Shared {
+         fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buffer)
+         }
+         fn flush(&mut self) -> io::Result<()> {
+            Ok(())
          }
-         return self.process_turn();
-      }
-      if self.my_robot.get_location() != &module::Module::Diagnosis {
-         return command::Command::Goto(module::Module::Diagnosis);
       }
 
-      return command::Command::Connect(connect_options::ConnectOptions::SampleId(
-         samples_to_drop[0].get_id(),
-      ));
+      let shared = Shared::default();
+      let mut memory = Memory::new();
+      memory.set_exporter(Exporter::new(Box::new(shared.clone())));
+      let me = RobotBuilder::at(Module::Molecule).sample(SampleBuilder::new(3).health(10).cost("A1")).build();
+      let state = TurnState::new(0, me, RobotBuilder::at(Module::Spawn).build(), Vec::new(), molecules("A5"));
+      memory.observe(state, Instant::now());
+
+      let out_of_stock = Command::Connect(ConnectOptions::MoleculeType(Molecule::E));
+      let sent = memory.finish_turn(out_of_stock.into());
+      assert_eq!(sent.get_message(), Some("FALLBACK"));
+      let exported = String::from_utf8(shared.0.borrow().clone()).unwrap();
+      assert!(exported.contains(&format!(r#""command":"{}","message":"FALLBACK""#, sent.get_action())), "{}", exported);
    }
 }
@@ -1,4 +1,6 @@
-#[derive(Debug, PartialEq)]
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Module {
    Sample,
    Diagnosis,
@@ -8,20 +10,27 @@ pub enum Module {
 }
 
 impl Module {
+   pub const ALL: [Module; 5] = [
+      Module::Sample,
+      Module::Diagnosis,
+      Module::Molecule,
+      Module::Laboratory,
+      Module::Spawn,
+   ];
+
    const SAMPLE: &str = "SAMPLES";
    const DIAGNOSIS: &str = "DIAGNOSIS";
    const MOLECULES: &str = "MOLECULES";
    const LABORATORY: &str = "LABORATORY";
    const SPAWN: &str = "START_POS";
 
-   pub fn from_str(s: &str) -> Result<Self, &'static str> {
-      match s {
-         Self::SAMPLE => Ok(Module::Sample),
-         Self::DIAGNOSIS => Ok(Module::Diagnosis),
-         Self::MOLECULES => Ok(Module::Molecule),
-         Self::LABORATORY => Ok(Module::Laboratory),
-         Self::SPAWN => Ok(Module::Spawn),
-         _ => Err("Invalid module name"),
+   pub fn index(&self) -> usize {
+      match self {
+         Module::Sample => 0,
+         Module::Diagnosis => 1,
+         Module::Molecule => 2,
+         Module::Laboratory => 3,
+         Module::Spawn => 4,
       }
    }
 
@@ -35,3 +44,18 @@ impl Module {
       }
    }
 }
+
+impl FromStr for Module {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      match s {
+         Self::SAMPLE => Ok(Module::Sample),
+         Self::DIAGNOSIS => Ok(Module::Diagnosis),
+         Self::MOLECULES => Ok(Module::Molecule),
+         Self::LABORATORY => Ok(Module::Laboratory),
+         Self::SPAWN => Ok(Module::Spawn),
+         _ => Err("Invalid module name"),
+      }
+   }
+}
@@ -1,6 +1,8 @@
-use std::ops::{Add, Sub};
+use super::pickup_policy;
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Sub};
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Molecule {
    A,
    B,
@@ -9,16 +11,14 @@ pub enum Molecule {
    E,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Molecules {
-   a: i8,
-   b: i8,
-   c: i8,
-   d: i8,
-   e: i8,
+   counts: [i8; 5],
 }
 
 impl Molecule {
+   pub const ALL: [Molecule; 5] = [Molecule::A, Molecule::B, Molecule::C, Molecule::D, Molecule::E];
+
    pub fn as_char(&self) -> char {
       match self {
          Molecule::A => 'A',
@@ -28,115 +28,203 @@ impl Molecule {
          Molecule::E => 'E',
       }
    }
+
+   pub fn from_char(letter: char) -> Option<Self> {
+      match letter {
+         'A' => Some(Molecule::A),
+         'B' => Some(Molecule::B),
+         'C' => Some(Molecule::C),
+         'D' => Some(Molecule::D),
+         'E' => Some(Molecule::E),
+         _ => None,
+      }
+   }
+
+   pub fn index(&self) -> usize {
+      *self as usize
+   }
+}
+
+impl fmt::Display for Molecule {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "{}", self.as_char())
+   }
 }
 
 impl Molecules {
    const MIN_CONSTRUCTOR_SLICE_LENGTH: usize = 5;
    pub fn new() -> Self {
-      Self {
-         a: 0,
-         b: 0,
-         c: 0,
-         d: 0,
-         e: 0,
-      }
+      Self { counts: [0; 5] }
+   }
+
+   pub fn from_array(counts: [i8; 5]) -> Self {
+      Self { counts }
    }
 
    pub fn from_slice(slice: &[&str]) -> Self {
       if slice.len() >= Self::MIN_CONSTRUCTOR_SLICE_LENGTH {
-         Self {
-               a: parse_input!(slice[0], i8),
-               b: parse_input!(slice[1], i8),
-               c: parse_input!(slice[2], i8),
-               d: parse_input!(slice[3], i8),
-               e: parse_input!(slice[4], i8),
+         let mut molecules = Molecules::new();
+         for molecule in Molecule::ALL {
+            molecules[molecule] = parse_input!(slice[molecule.index()], i8);
          }
+         molecules
       } else {
          panic!("Tried reading molecule from a short slice");
       }
    }
 
-   pub fn len(&self) -> i8 {
-      self.a + self.b + self.c + self.d + self.e
+   pub fn as_array(&self) -> &[i8; 5] {
+      &self.counts
+   }
+
+   // every molecule type paired with its count, in A to E order
+   pub fn iter(&self) -> impl Iterator<Item = (Molecule, i8)> + '_ {
+      Molecule::ALL.iter().map(move |molecule| (*molecule, self[*molecule]))
+   }
+
+   pub fn len(&self) -> i16 {
+      self.counts.iter().map(|count| *count as i16).sum()
+   }
+
+   pub fn is_empty(&self) -> bool {
+      self.len() == 0
    }
 
    pub fn is_not_positive(&self) -> bool {
-      self.a <= 0 && self.b <= 0 && self.c <= 0 && self.d <= 0 && self.e <= 0
+      self.counts.iter().all(|count| *count <= 0)
    }
 
    pub fn from_letter(letter: char) -> Self {
       let mut molecules = Molecules::new();
-      match letter {
-         'A' => molecules.a = 1,
-         'B' => molecules.b = 1,
-         'C' => molecules.c = 1,
-         'D' => molecules.d = 1,
-         'E' => molecules.e = 1,
-         _ => {}
+      if let Some(molecule) = Molecule::from_char(letter) {
+         molecules[molecule] = 1;
       }
       molecules
    }
 
    pub fn set_minues_to_zero(&self) -> Molecules {
-      let mut new_non_zero = self.clone();
-      if new_non_zero.a < 0 {
-         new_non_zero.a = 0;
-      }
-      if new_non_zero.b < 0 {
-         new_non_zero.b = 0;
-      }
-      if new_non_zero.c < 0 {
-         new_non_zero.c = 0;
-      }
-      if new_non_zero.d < 0 {
-         new_non_zero.d = 0;
-      }
-      if new_non_zero.e < 0 {
-         new_non_zero.e = 0;
-      }
-      new_non_zero
+      self.map(|count| count.max(0))
    }
 
    pub fn has_enough(&self, required: &Molecules) -> bool {
-      if self.a - required.a < 0 {
-         return false;
-      }
-      if self.b - required.b < 0 {
-         return false;
-      }
-      if self.c - required.c < 0 {
-         return false;
-      }
-      if self.d - required.d < 0 {
-         return false;
-      }
-      if self.e - required.e < 0 {
-         return false;
-      }
-      true
+      Molecule::ALL
+         .iter()
+         .all(|molecule| self[*molecule] >= required[*molecule])
+   }
+
+   // the positive type to pick up first, `PickupPolicy::fixed_order` for plain A to E
+   pub fn get_next_molecule(&self, policy: &pickup_policy::PickupPolicy) -> Option<Molecule> {
+      policy.pick(self)
+   }
+
+   pub fn has_any_negatives(&self) -> bool {
+      self.counts.iter().any(|count| *count < 0)
    }
 
-   pub fn get_next_molecule(&self) -> Option<Molecule> {
-      if self.a > 0 {
-         return Some(Molecule::A);
+   // type with the highest count, the earliest type wins ties
+   pub fn max_molecule(&self) -> Molecule {
+      let mut best = Molecule::A;
+      for (molecule, count) in self.iter() {
+         if count > self[best] {
+            best = molecule;
+         }
       }
-      if self.b > 0 {
-         return Some(Molecule::B);
+      best
+   }
+
+   // type with the lowest count, the earliest type wins ties
+   pub fn min_molecule(&self) -> Molecule {
+      let mut best = Molecule::A;
+      for (molecule, count) in self.iter() {
+         if count < self[best] {
+            best = molecule;
+         }
       }
-      if self.c > 0 {
-         return Some(Molecule::C);
+      best
+   }
+
+   pub fn min(&self, other: &Molecules) -> Molecules {
+      self.zip(other, |mine, theirs| mine.min(theirs))
+   }
+
+   pub fn max(&self, other: &Molecules) -> Molecules {
+      self.zip(other, |mine, theirs| mine.max(theirs))
+   }
+
+   pub fn dot(&self, other: &Molecules) -> i32 {
+      self.counts
+         .iter()
+         .zip(other.counts.iter())
+         .map(|(mine, theirs)| *mine as i32 * *theirs as i32)
+         .sum()
+   }
+
+   fn map<F: Fn(i8) -> i8>(&self, operation: F) -> Molecules {
+      let mut result = self.clone();
+      for count in result.counts.iter_mut() {
+         *count = operation(*count);
       }
-      if self.d > 0 {
-         return Some(Molecule::D);
+      result
+   }
+
+   fn zip<F: Fn(i8, i8) -> i8>(&self, other: &Molecules, operation: F) -> Molecules {
+      let mut result = Molecules::new();
+      for molecule in Molecule::ALL {
+         result[molecule] = operation(self[molecule], other[molecule]);
       }
-      if self.e > 0 {
-         return Some(Molecule::E);
+      result
+   }
+
+   fn try_zip<F: Fn(i8, i8) -> Option<i8>>(&self, other: &Molecules, operation: F) -> Option<Molecules> {
+      let mut result = Molecules::new();
+      for molecule in Molecule::ALL {
+         result[molecule] = operation(self[molecule], other[molecule])?;
       }
-      return None;
+      Some(result)
    }
 
-   pub fn has_any_negatives(&self) -> bool {
-      self.a < 0 || self.b < 0 || self.c < 0 || self.d < 0 || self.e < 0
+   // `+` and `-` clamp to the `i8` range, these say when that would happen
+   pub fn checked_add(&self, other: &Molecules) -> Option<Molecules> {
+      self.try_zip(other, i8::checked_add)
+   }
+   pub fn checked_sub(&self, other: &Molecules) -> Option<Molecules> {
+      self.try_zip(other, i8::checked_sub)
+   }
+}
+
+impl Default for Molecules {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+impl Index<Molecule> for Molecules {
+   type Output = i8;
+
+   fn index(&self, molecule: Molecule) -> &i8 {
+      &self.counts[molecule.index()]
+   }
+}
+
+impl IndexMut<Molecule> for Molecules {
+   fn index_mut(&mut self, molecule: Molecule) -> &mut i8 {
+      &mut self.counts[molecule.index()]
+   }
+}
+
+// non-zero counts as letter/count pairs, e.g. "A2 B1", or "-" when empty
+impl fmt::Display for Molecules {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let parts = self
+         .iter()
+         .filter(|(_, count)| *count != 0)
+         .map(|(molecule, count)| format!("{}{}", molecule, count))
+         .collect::<Vec<_>>();
+      if parts.is_empty() {
+         write!(f, "-")
+      } else {
+         write!(f, "{}", parts.join(" "))
+      }
    }
 }
 
@@ -144,13 +232,7 @@ impl Add<&Molecules> for &Molecules {
    type Output = Molecules;
 
    fn add(self, other: &Molecules) -> Molecules {
-      Molecules {
-         a: self.a + other.a,
-         b: self.b + other.b,
-         c: self.c + other.c,
-         d: self.d + other.d,
-         e: self.e + other.e,
-      }
+      self.zip(other, i8::saturating_add)
    }
 }
 
@@ -158,12 +240,157 @@ impl Sub<&Molecules> for &Molecules {
    type Output = Molecules;
 
    fn sub(self, other: &Molecules) -> Molecules {
-      Molecules {
-         a: self.a - other.a,
-         b: self.b - other.b,
-         c: self.c - other.c,
-         d: self.d - other.d,
-         e: self.e - other.e,
+      self.zip(other, i8::saturating_sub)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::pickup_policy::PickupPolicy;
+   use super::super::random::Random;
+   use super::{Molecule, Molecules};
+
+   const CASES: usize = 2000;
+   const SEED: u64 = 0xC0DE_411F_E000;
+
+   struct Generator(Random);
+
+   impl Generator {
+      fn in_range(&mut self, low: i8, high: i8) -> i8 {
+         self.0.between(low as i64, high as i64) as i8
+      }
+
+      // counts seen in real games: stock, inventory, costs, expertise and their differences
+      fn realistic(&mut self) -> Molecules {
+         self.molecules(-20, 20)
+      }
+
+      fn any(&mut self) -> Molecules {
+         self.molecules(i8::MIN, i8::MAX)
+      }
+
+      fn molecules(&mut self, low: i8, high: i8) -> Molecules {
+         let mut molecules = Molecules::new();
+         for molecule in Molecule::ALL {
+            molecules[molecule] = self.in_range(low, high);
+         }
+         molecules
       }
    }
+
+   fn fields(molecules: &Molecules) -> [i8; 5] {
+      *molecules.as_array()
+   }
+
+   fn for_all(mut property: impl FnMut(&mut Generator)) {
+      let mut generator = Generator(Random::new(SEED));
+      for _ in 0..CASES {
+         property(&mut generator);
+      }
+   }
+
+   #[test]
+   fn addition_is_commutative_and_has_zero_identity() {
+      for_all(|generator| {
+         let (x, y) = (generator.realistic(), generator.realistic());
+         assert_eq!(fields(&(&x + &y)), fields(&(&y + &x)));
+         assert_eq!(fields(&(&x + &Molecules::new())), fields(&x));
+         assert_eq!(fields(&(&x - &Molecules::new())), fields(&x));
+      });
+   }
+
+   #[test]
+   fn subtraction_undoes_addition() {
+      for_all(|generator| {
+         let (x, y) = (generator.realistic(), generator.realistic());
+         assert_eq!(fields(&(&(&x + &y) - &y)), fields(&x));
+         assert!((&x - &x).is_empty() && !(&x - &x).has_any_negatives());
+      });
+   }
+
+   #[test]
+   fn len_is_additive() {
+      for_all(|generator| {
+         let (x, y) = (generator.realistic(), generator.realistic());
+         assert_eq!((&x + &y).len(), x.len() + y.len());
+      });
+   }
+
+   #[test]
+   fn set_minues_to_zero_clamps_only_negatives() {
+      for_all(|generator| {
+         let x = generator.realistic();
+         let clamped = x.set_minues_to_zero();
+         assert!(!clamped.has_any_negatives());
+         assert_eq!(fields(&clamped.set_minues_to_zero()), fields(&clamped));
+         for (original, clamped) in fields(&x).iter().zip(fields(&clamped).iter()) {
+            assert_eq!(*clamped, (*original).max(0));
+         }
+         assert_eq!(x.is_not_positive(), clamped.is_empty());
+      });
+   }
+
+   #[test]
+   fn has_enough_matches_difference_sign() {
+      for_all(|generator| {
+         let (held, required) = (generator.realistic(), generator.realistic());
+         assert_eq!(held.has_enough(&required), !(&held - &required).has_any_negatives());
+         assert!(held.has_enough(&held));
+         assert!((&held + &required.set_minues_to_zero()).has_enough(&held));
+      });
+   }
+
+   #[test]
+   fn extreme_values_saturate_instead_of_overflowing() {
+      let top = Molecules::from_array([i8::MAX, 0, 0, 0, 0]);
+      let bottom = Molecules::from_array([0, i8::MIN, 0, 0, 0]);
+      assert_eq!(top.checked_add(&Molecules::from_array([1, 0, 0, 0, 0])), None);
+      assert_eq!(bottom.checked_sub(&Molecules::from_array([0, 1, 0, 0, 0])), None);
+      assert_eq!(Molecules::from_array([1; 5]).checked_sub(&Molecules::from_array([2; 5])), Some(Molecules::from_array([-1; 5])));
+      for_all(|generator| {
+         let (x, y) = (generator.any(), generator.any());
+         let sum = &x + &y;
+         let difference = &x - &y;
+         assert!(x.checked_add(&y).is_none_or(|checked| checked == sum));
+         assert!(x.checked_sub(&y).is_none_or(|checked| checked == difference));
+         for (index, (x, y)) in fields(&x).iter().zip(fields(&y).iter()).enumerate() {
+            assert_eq!(fields(&sum)[index], x.saturating_add(*y));
+            assert_eq!(fields(&difference)[index], x.saturating_sub(*y));
+         }
+         let _ = (x.len(), x.is_empty(), x.is_not_positive(), x.has_any_negatives());
+         let _ = (x.has_enough(&y), x.set_minues_to_zero(), x.get_next_molecule(&PickupPolicy::fixed_order()));
+      });
+   }
+
+   #[test]
+   fn indexing_follows_molecule_order() {
+      let mut molecules = Molecules::from_array([1, 2, 3, 4, 5]);
+      for (position, molecule) in Molecule::ALL.iter().enumerate() {
+         assert_eq!(molecule.index(), position);
+         assert_eq!(Molecule::from_char(molecule.as_char()), Some(*molecule));
+         assert_eq!(molecules[*molecule], position as i8 + 1);
+      }
+      molecules[Molecule::C] -= 3;
+      assert_eq!(molecules.as_array(), &[1, 2, 0, 4, 5]);
+      assert_eq!(molecules.get_next_molecule(&PickupPolicy::fixed_order()), Some(Molecule::A));
+   }
+
+   #[test]
+   fn component_wise_helpers() {
+      let x = Molecules::from_array([3, 0, 2, 0, 1]);
+      let y = Molecules::from_array([1, 4, 2, 0, -1]);
+      assert_eq!(x.min(&y), Molecules::from_array([1, 0, 2, 0, -1]));
+      assert_eq!(x.max(&y), Molecules::from_array([3, 4, 2, 0, 1]));
+      assert_eq!(x.dot(&y), 3 + 4 - 1);
+      assert_eq!(x.max_molecule(), Molecule::A);
+      assert_eq!(x.min_molecule(), Molecule::B);
+      assert_eq!(y.max_molecule(), Molecule::B);
+      assert_eq!(y.min_molecule(), Molecule::E);
+   }
+
+   #[test]
+   fn display_lists_non_zero_counts() {
+      assert_eq!(Molecules::from_array([2, 1, 0, 0, -1]).to_string(), "A2 B1 E-1");
+      assert_eq!(Molecules::new().to_string(), "-");
+   }
 }
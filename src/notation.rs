@@ -0,0 +1,274 @@
+/*!
+ * One line notation for a whole position, to paste into tests and issues.
+ *
+ * ```text
+ * 12; DIAGNOSIS 0 10 A2B1 C1; MOLECULES 2 0 - -; A5B4C5D5E5; A3B3C3 A4C4; 3:m:1:10:A1B2:C 4:c:2:?:?:A
+ * ```
+ *
+ * Sections are separated by `;`: the turn, our robot and the enemy robot
+ * (module, eta, score, inventory, expertise), the available stock, the
+ * projects and the samples. Molecule sets are written without spaces, `-`
+ * when empty. A sample is `id:owner:rank:health:cost:gain`, the owner being
+ * `m` (me), `e` (enemy) or `c` (cloud), health and cost `?` until diagnosed.
+ */
+
+use super::carried_by;
+use super::history;
+use super::module;
+use super::molecules;
+use super::robot;
+use super::sample;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug)]
+pub struct Position {
+   state: history::TurnState,
+   projects: Vec<molecules::Molecules>,
+}
+
+impl Position {
+   pub fn new(state: history::TurnState, projects: Vec<molecules::Molecules>) -> Self {
+      Self { state, projects }
+   }
+   pub fn get_state(&self) -> &history::TurnState {
+      &self.state
+   }
+   pub fn get_projects(&self) -> &Vec<molecules::Molecules> {
+      &self.projects
+   }
+}
+
+// "A2B1", "-" when empty
+fn format_molecules(molecules: &molecules::Molecules) -> String {
+   if molecules.iter().all(|(_, count)| count == 0) {
+      return "-".to_owned();
+   }
+   molecules
+      .iter()
+      .filter(|(_, count)| *count != 0)
+      .map(|(molecule, count)| format!("{}{}", molecule, count))
+      .collect()
+}
+
+// a letter without a count stands for one molecule
+fn parse_molecules(text: &str) -> Result<molecules::Molecules, &'static str> {
+   let mut result = molecules::Molecules::new();
+   if text == "-" {
+      return Ok(result);
+   }
+   let mut chars = text.chars().peekable();
+   while let Some(letter) = chars.next() {
+      let molecule = molecules::Molecule::from_char(letter).ok_or("Invalid molecule letter")?;
+      let mut digits = String::new();
+      while let Some(digit) = chars.next_if(|next| next.is_ascii_digit() || *next == '-') {
+         digits.push(digit);
+      }
+      result[molecule] = if digits.is_empty() {
+         1
+      } else {
+         digits.parse().map_err(|_| "Invalid molecule count")?
+      };
+   }
+   Ok(result)
+}
+
+fn format_robot(robot: &robot::Robot) -> String {
+   format!(
+      "{} {} {} {} {}",
+      robot.get_location().as_str(),
+      robot.get_eta(),
+      robot.get_score(),
+      format_molecules(robot.get_inventory()),
+      format_molecules(robot.get_expertise())
+   )
+}
+
+fn parse_robot(text: &str) -> Result<robot::Robot, &'static str> {
+   let fields = text.split_whitespace().collect::<Vec<_>>();
+   if fields.len() != 5 {
+      return Err("A robot needs module, eta, score, inventory and expertise");
+   }
+   Ok(robot::Robot::from_parts(
+      fields[0].parse::<module::Module>()?,
+      fields[1].parse().map_err(|_| "Invalid eta")?,
+      fields[2].parse().map_err(|_| "Invalid score")?,
+      parse_molecules(fields[3])?,
+      parse_molecules(fields[4])?,
+      Vec::new(),
+   ))
+}
+
+fn format_sample(owner: carried_by::CarriedBy, sample: &sample::Sample) -> String {
+   let owner = match owner {
+      carried_by::CarriedBy::Me => 'm',
+      carried_by::CarriedBy::Other => 'e',
+      carried_by::CarriedBy::Cloud => 'c',
+   };
+   let (health, cost) = match sample.get_health() {
+      sample::SampleHealth::Researched(health) => (health.to_string(), format_molecules(sample.get_cost())),
+      sample::SampleHealth::Unresearched => ("?".to_owned(), "?".to_owned()),
+   };
+   let gain = sample.get_expertise_gain();
+   let gain = if gain.is_empty() { '-' } else { gain.max_molecule().as_char() };
+   format!("{}:{}:{}:{}:{}:{}", sample.get_id(), owner, sample.get_rank().as_value(), health, cost, gain)
+}
+
+fn parse_sample(text: &str) -> Result<(carried_by::CarriedBy, sample::Sample), &'static str> {
+   let fields = text.split(':').collect::<Vec<_>>();
+   if fields.len() != 6 {
+      return Err("A sample needs id:owner:rank:health:cost:gain");
+   }
+   let owner = match fields[1] {
+      "m" => carried_by::CarriedBy::Me,
+      "e" => carried_by::CarriedBy::Other,
+      "c" => carried_by::CarriedBy::Cloud,
+      _ => return Err("Invalid sample owner"),
+   };
+   let rank = fields[2].parse::<i8>().ok().and_then(sample::SampleRank::from_integer).ok_or("Invalid sample rank")?;
+   // undiagnosed samples look the way the referee sends them
+   let (health, cost) = match (fields[3], fields[4]) {
+      ("?", "?") => (sample::SampleHealth::Unresearched, molecules::Molecules::from_array([-1; 5])),
+      ("?", _) | (_, "?") => return Err("Health and cost are unknown together"),
+      (health, cost) => (
+         sample::SampleHealth::Researched(health.parse().map_err(|_| "Invalid sample health")?),
+         parse_molecules(cost)?,
+      ),
+   };
+   let gain = match fields[5] {
+      "-" => molecules::Molecules::new(),
+      letter => parse_molecules(letter)?,
+   };
+   let id = fields[0].parse().map_err(|_| "Invalid sample id")?;
+   Ok((owner, sample::Sample::new(id, rank, health, cost, gain)))
+}
+
+impl fmt::Display for Position {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let state = &self.state;
+      let projects = self.projects.iter().map(format_molecules).collect::<Vec<_>>();
+      let mut samples = Vec::new();
+      for (owner, robot) in [
+         (carried_by::CarriedBy::Me, state.get_my_robot()),
+         (carried_by::CarriedBy::Other, state.get_enemy_robot()),
+      ] {
+         samples.extend(robot.get_held_samples().iter().map(|sample| format_sample(owner, sample)));
+      }
+      samples.extend(state.get_cloud().iter().map(|sample| format_sample(carried_by::CarriedBy::Cloud, sample)));
+      write!(
+         f,
+         "{}; {}; {}; {}; {}; {}",
+         state.get_turn(),
+         format_robot(state.get_my_robot()),
+         format_robot(state.get_enemy_robot()),
+         format_molecules(state.get_available()),
+         if projects.is_empty() { "-".to_owned() } else { projects.join(" ") },
+         if samples.is_empty() { "-".to_owned() } else { samples.join(" ") }
+      )
+   }
+}
+
+impl FromStr for Position {
+   type Err = &'static str;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let sections = s.split(';').map(str::trim).collect::<Vec<_>>();
+      if sections.len() != 6 {
+         return Err("Expected turn; me; enemy; available; projects; samples");
+      }
+      let turn = sections[0].parse().map_err(|_| "Invalid turn")?;
+      let mut my_robot = parse_robot(sections[1])?;
+      let mut enemy_robot = parse_robot(sections[2])?;
+      let available = parse_molecules(sections[3])?;
+      let projects = match sections[4] {
+         "-" => Vec::new(),
+         projects => projects.split_whitespace().map(parse_molecules).collect::<Result<Vec<_>, _>>()?,
+      };
+      let mut cloud = Vec::new();
+      for text in sections[5].split_whitespace().filter(|text| *text != "-") {
+         match parse_sample(text)? {
+            (carried_by::CarriedBy::Me, sample) => my_robot.append_sample(sample),
+            (carried_by::CarriedBy::Other, sample) => enemy_robot.append_sample(sample),
+            (carried_by::CarriedBy::Cloud, sample) => cloud.push(sample),
+         }
+      }
+      Ok(Position::new(history::TurnState::new(turn, my_robot, enemy_robot, cloud, available), projects))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::history::TurnState;
+   use super::super::input_reading;
+   use super::super::memory::Memory;
+   use super::super::molecules::Molecule;
+   use super::super::sample::SampleHealth;
+   use super::{format_molecules, parse_molecules, Position};
+   use std::io::Cursor;
+   use std::time::Instant;
+
+   const EXAMPLE: &str =
+      "12; DIAGNOSIS 0 10 A2B1 C1; MOLECULES 2 0 - -; A5B4C5D5E5; A3B3C3 A4C4; 3:m:1:10:A1B2:C 4:c:2:?:?:A";
+
+   #[test]
+   fn round_trips() {
+      let position = EXAMPLE.parse::<Position>().unwrap();
+      assert_eq!(position.to_string(), EXAMPLE);
+      let state = position.get_state();
+      assert_eq!(state.get_turn(), 12);
+      assert_eq!(state.get_my_robot().get_inventory()[Molecule::A], 2);
+      assert_eq!(state.get_cloud()[0].get_health(), &SampleHealth::Unresearched);
+      assert_eq!(position.get_projects().len(), 2);
+
+      let empty = "0; START_POS 0 0 - -; START_POS 0 0 - -; A5B5C5D5E5; -; -";
+      assert_eq!(empty.parse::<Position>().unwrap().to_string(), empty);
+
+      // counts that cancel out are still written
+      let cancelling = parse_molecules("A1B-1").unwrap();
+      assert_eq!(format_molecules(&cancelling), "A1B-1");
+      assert_eq!(parse_molecules(&format_molecules(&cancelling)), Ok(cancelling));
+   }
+
+   #[test]
+   fn matches_what_the_referee_sends() {
+      let input = "SAMPLES 0 3 1 0 0 0 0 0 0 0 0 1\n\
+         DIAGNOSIS 2 0 0 0 0 0 0 0 0 0 0 0\n\
+         4 5 5 5 5\n\
+         2\n\
+         0 0 1 A -1 -1 -1 -1 -1 -1\n\
+         1 -1 2 E 20 0 3 0 2 0\n";
+      let (my_robot, enemy_robot, cloud, available, _) = input_reading::parse_turn_input_from(&mut Cursor::new(input));
+      let parsed = Position::new(TurnState::new(7, my_robot, enemy_robot, cloud, available), vec![]);
+      let text = parsed.to_string();
+      assert_eq!(text, "7; SAMPLES 0 3 A1 E1; DIAGNOSIS 2 0 - -; A4B5C5D5E5; -; 0:m:1:?:?:A 1:c:2:20:B3D2:E");
+      let reparsed = text.parse::<Position>().unwrap();
+      assert_eq!(reparsed.get_state().get_my_robot().get_held_samples()[0].get_cost(), parsed.get_state().get_my_robot().get_held_samples()[0].get_cost());
+   }
+
+   #[test]
+   fn rejects_malformed_notation() {
+      for text in [
+         "",
+         "1; START_POS 0 0 - -; START_POS 0 0 - -; -; -",
+         "x; START_POS 0 0 - -; START_POS 0 0 - -; -; -; -",
+         "1; KITCHEN 0 0 - -; START_POS 0 0 - -; -; -; -",
+         "1; START_POS 0 0 F2 -; START_POS 0 0 - -; -; -; -",
+         "1; START_POS 0 0 - -; START_POS 0 0 - -; -; -; 3:x:1:?:?:A",
+         "1; START_POS 0 0 - -; START_POS 0 0 - -; -; -; 3:m:4:?:?:A",
+         "1; START_POS 0 0 - -; START_POS 0 0 - -; -; -; 3:m:1:?:A1:A",
+      ] {
+         assert!(text.parse::<Position>().is_err(), "{}", text);
+      }
+   }
+
+   #[test]
+   fn reproduces_a_decision() {
+      let position = "40; DIAGNOSIS 0 20 A1 -; SAMPLES 0 0 - -; A5B5C5D5E5; A3B3C3; 12:m:1:?:?:B 13:m:1:?:?:C 14:m:2:?:?:D"
+         .parse::<Position>()
+         .unwrap();
+      let mut memory = Memory::new();
+      memory.load_position(&position, Instant::now());
+      assert_eq!(memory.get_tables().get_projects().len(), 1);
+      assert_eq!(memory.process_turn().get_action().to_string(), "CONNECT 12");
+   }
+}
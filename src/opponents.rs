@@ -0,0 +1,578 @@
+/*!
+ * Opponents of known behaviour to benchmark strategies against.
+ *
+ * Each one is deliberately simple and stable, so a change in win rate against
+ * it means the strategy under test changed, not the opponent.
+ */
+
+use super::command;
+use super::connect_options;
+use super::history;
+use super::module;
+use super::molecules;
+use super::pickup_policy;
+use super::random;
+use super::robot;
+use super::sample;
+use super::strategy;
+
+// go to `module` first if needed, then connect with `options`
+fn connect_at(robot: &robot::Robot, module: module::Module, options: connect_options::ConnectOptions) -> command::Command {
+   if robot.get_location() != &module {
+      command::Command::Goto(module)
+   } else {
+      command::Command::Connect(options)
+   }
+}
+
+fn wait_at(robot: &robot::Robot, module: module::Module) -> command::Command {
+   if robot.get_location() != &module {
+      command::Command::Goto(module)
+   } else {
+      command::Command::Wait
+   }
+}
+
+fn is_researched(sample: &sample::Sample) -> bool {
+   matches!(sample.get_health(), sample::SampleHealth::Researched(_))
+}
+
+/**
+ * Plain production loop over the samples already held: produce what is
+ * complete, diagnose what is not, drop what can never be finished and gather
+ * the rest in A to E order. None once nothing is held.
+ */
+fn work_on_held_samples(robot: &robot::Robot, available: &molecules::Molecules) -> Option<command::Command> {
+   let covered = robot.get_inventory() + robot.get_expertise();
+   let held = robot.get_held_samples();
+   if let Some(complete) = held
+      .iter()
+      .find(|sample| is_researched(sample) && (sample.get_cost() - &covered).is_not_positive())
+   {
+      let options = connect_options::ConnectOptions::SampleId(complete.get_id());
+      return Some(connect_at(robot, module::Module::Laboratory, options));
+   }
+   if let Some(unresearched) = robot.get_unresearched_sample() {
+      let options = connect_options::ConnectOptions::SampleId(unresearched.get_id());
+      return Some(connect_at(robot, module::Module::Diagnosis, options));
+   }
+   if let Some(impossible) = robot.get_impossible_samples(available).first() {
+      let options = connect_options::ConnectOptions::SampleId(impossible.get_id());
+      return Some(connect_at(robot, module::Module::Diagnosis, options));
+   }
+   if held.is_empty() {
+      return None;
+   }
+   match robot.pick_best_molecule(available, &pickup_policy::PickupPolicy::fixed_order()) {
+      Some(molecule) => {
+         let options = connect_options::ConnectOptions::MoleculeType(molecule);
+         Some(connect_at(robot, module::Module::Molecule, options))
+      }
+      // the stock may come back once the enemy produces
+      None => Some(command::Command::Wait),
+   }
+}
+
+/**
+ * Picks uniformly among the legal commands. The sequence comes from a fixed
+ * seed, or from the game's seed in a simulation, so that games can be replayed.
+ */
+#[derive(Debug)]
+pub struct RandomLegalStrategy {
+   random: random::Random,
+}
+
+impl RandomLegalStrategy {
+   pub const NAME: &'static str = "random_legal";
+   pub const DEFAULT_SEED: u64 = 0x5EED_C0DE_411F;
+
+   pub fn new(seed: u64) -> Self {
+      Self {
+         random: random::Random::new(seed),
+      }
+   }
+}
+
+impl strategy::Strategy for RandomLegalStrategy {
+   fn name(&self) -> &'static str {
+      Self::NAME
+   }
+
+   fn set_random(&mut self, random: random::Random) {
+      self.random = random;
+   }
+
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      let state = context.get_state();
+      let mut legal = command::Command::legal_commands(state.get_my_robot(), state.get_available(), state.get_cloud());
+      let index = self.random.below(legal.len());
+      legal.swap_remove(index).into()
+   }
+}
+
+/**
+ * Only ever draws rank 1 samples, three at a time, and completes them before
+ * drawing again.
+ */
+#[derive(Debug, Default)]
+pub struct GreedyRankOneStrategy {}
+
+impl GreedyRankOneStrategy {
+   pub const NAME: &'static str = "greedy_rank_one";
+
+   pub fn new() -> Self {
+      Self {}
+   }
+}
+
+impl strategy::Strategy for GreedyRankOneStrategy {
+   fn name(&self) -> &'static str {
+      Self::NAME
+   }
+
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      let state = context.get_state();
+      let robot = state.get_my_robot();
+      if robot.get_eta() > 0 {
+         return command::Command::Wait.into();
+      }
+      // keep drawing while still standing at SAMPLES with free hands
+      let drawing = robot.get_location() == &module::Module::Sample || robot.get_held_samples().is_empty();
+      if drawing && !robot.has_maximum_samples() {
+         let options = connect_options::ConnectOptions::SampleRank(sample::SampleRank::LittleHealth);
+         return connect_at(robot, module::Module::Sample, options).into();
+      }
+      work_on_held_samples(robot, state.get_available()).unwrap_or(command::Command::Wait).into()
+   }
+}
+
+/**
+ * Never produces anything: parks at MOLECULES and hoards the types the enemy
+ * still needs, the scarcest first, until its hands are full.
+ */
+#[derive(Debug, Default)]
+pub struct MoleculeBlockerStrategy {}
+
+impl MoleculeBlockerStrategy {
+   pub const NAME: &'static str = "molecule_blocker";
+
+   pub fn new() -> Self {
+      Self {}
+   }
+
+   fn pick(state: &history::TurnState) -> Option<molecules::Molecule> {
+      let available = state.get_available();
+      let enemy_needs = state.get_enemy_robot().get_outstanding_needs();
+      molecules::Molecule::ALL
+         .into_iter()
+         .filter(|molecule| available[*molecule] > 0)
+         .max_by_key(|molecule| (enemy_needs[*molecule], -available[*molecule]))
+   }
+}
+
+impl strategy::Strategy for MoleculeBlockerStrategy {
+   fn name(&self) -> &'static str {
+      Self::NAME
+   }
+
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      let state = context.get_state();
+      let robot = state.get_my_robot();
+      if robot.get_eta() > 0 || robot.has_maximum_molecules() {
+         return command::Command::Wait.into();
+      }
+      match Self::pick(state) {
+         Some(molecule) => {
+            connect_at(robot, module::Module::Molecule, connect_options::ConnectOptions::MoleculeType(molecule))
+         }
+         None => wait_at(robot, module::Module::Molecule),
+      }
+      .into()
+   }
+}
+
+/**
+ * Never draws a sample itself: waits at DIAGNOSIS for diagnosed samples the
+ * enemy drops in the cloud and finishes the ones it can afford.
+ */
+#[derive(Debug, Default)]
+pub struct CloudSniperStrategy {}
+
+impl CloudSniperStrategy {
+   pub const NAME: &'static str = "cloud_sniper";
+
+   pub fn new() -> Self {
+      Self {}
+   }
+}
+
+impl strategy::Strategy for CloudSniperStrategy {
+   fn name(&self) -> &'static str {
+      Self::NAME
+   }
+
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      let state = context.get_state();
+      let robot = state.get_my_robot();
+      let available = state.get_available();
+      if robot.get_eta() > 0 {
+         return command::Command::Wait.into();
+      }
+      // snipe while passing by, or while there is nothing else to do
+      let idle = robot.get_held_samples().is_empty();
+      if idle || robot.get_location() == &module::Module::Diagnosis {
+         if let Some(target) = robot.pick_cloud_samples(state.get_cloud(), available).first() {
+            let options = connect_options::ConnectOptions::SampleId(target.get_id());
+            return connect_at(robot, module::Module::Diagnosis, options).into();
+         }
+      }
+      work_on_held_samples(robot, available)
+         .unwrap_or_else(|| wait_at(robot, module::Module::Diagnosis))
+         .into()
+   }
+}
+
+#[derive(Debug)]
+enum BaselineGoal {
+   TakeSamples,
+   ResearchSamples,
+   GatherMolecules,
+   ProduceMedicine,
+   DropSamples,
+}
+
+/**
+ * The goal machine as it was when benchmarking started, speculative
+ * gathering off. It is a copy, robot heuristics and pickup policy included,
+ * so tuning `GoalMachineStrategy` or `Robot` cannot move the reference. Do
+ * not change it; add another opponent instead.
+ */
+#[derive(Debug)]
+pub struct BaselineStrategy {
+   goal: BaselineGoal,
+   // goal changes during the current decision
+   switches: u8,
+   error: Option<&'static str>,
+}
+
+impl Default for BaselineStrategy {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+impl BaselineStrategy {
+   pub const NAME: &'static str = "baseline";
+   const MAX_GOAL_SWITCHES: u8 = 6;
+   const MAX_SAMPLES: usize = 3;
+   const MAX_MOLECULES: i16 = 10;
+   const EXPERTISE_UNTIL_MIDDLE_RANK: i16 = 3;
+   const EXPERTISE_UNTIL_HIGH_RANK: i16 = 9;
+   const ENEMY_NEED_WEIGHT: i32 = 3;
+   const OUR_DEMAND_WEIGHT: i32 = 2;
+   const STOCK_WEIGHT: i32 = 2;
+   const CONTESTED_BONUS: i32 = 20;
+
+   pub fn new() -> Self {
+      Self {
+         goal: BaselineGoal::TakeSamples,
+         switches: 0,
+         error: None,
+      }
+   }
+
+   // best health first, held order among equals
+   fn sorted_samples(robot: &robot::Robot) -> Vec<&sample::Sample> {
+      let mut sorted = robot.get_held_samples().iter().collect::<Vec<_>>();
+      sorted.sort_by_key(|sample| match sample.get_health() {
+         sample::SampleHealth::Researched(health) => -(*health as i16),
+         sample::SampleHealth::Unresearched => 0,
+      });
+      sorted
+   }
+
+   fn unresearched_sample(robot: &robot::Robot) -> Option<&sample::Sample> {
+      robot.get_held_samples().iter().find(|sample| !is_researched(sample))
+   }
+
+   fn ready_sample(robot: &robot::Robot) -> Option<&sample::Sample> {
+      Self::sorted_samples(robot).into_iter().find(|sample| {
+         let cost = (sample.get_cost() - robot.get_expertise()).set_minues_to_zero();
+         robot.get_inventory().has_enough(&cost)
+      })
+   }
+
+   fn can_produce(robot: &robot::Robot, sample: &sample::Sample, available: &molecules::Molecules) -> bool {
+      let needed = sample.get_cost() - &(robot.get_expertise() + robot.get_inventory());
+      if needed.is_not_positive() {
+         return true;
+      }
+      let missing = needed.set_minues_to_zero();
+      missing.len() + robot.get_inventory().len() <= Self::MAX_MOLECULES && available.has_enough(&missing)
+   }
+
+   fn impossible_samples<'a>(robot: &'a robot::Robot, available: &molecules::Molecules) -> Vec<&'a sample::Sample> {
+      let held = robot.get_held_samples().iter();
+      held.filter(|sample| !Self::can_produce(robot, sample, available)).collect()
+   }
+
+   // whether the inventory covers every held sample, each spending its cost minus the expertise reached
+   fn has_enough_molecules(robot: &robot::Robot) -> bool {
+      let mut held = robot.get_inventory().clone();
+      let mut expertise = robot.get_expertise().clone();
+      for sample in Self::sorted_samples(robot) {
+         if !(sample.get_cost() - &(&expertise + &held)).is_not_positive() {
+            return false;
+         }
+         held = &held - &(sample.get_cost() - &expertise).set_minues_to_zero();
+         expertise = &expertise + sample.get_expertise_gain();
+      }
+      true
+   }
+
+   fn sample_rank(robot: &robot::Robot) -> sample::SampleRank {
+      let expertise = robot.get_expertise().len();
+      if expertise < Self::EXPERTISE_UNTIL_MIDDLE_RANK {
+         sample::SampleRank::LittleHealth
+      } else if expertise < Self::EXPERTISE_UNTIL_HIGH_RANK {
+         sample::SampleRank::SomeHealth
+      } else {
+         sample::SampleRank::LotsOfHealth
+      }
+   }
+
+   // how urgent it is to grab `molecule` before the stock or the enemy takes it
+   fn pickup_risk(state: &history::TurnState, molecule: molecules::Molecule) -> i32 {
+      let (my_robot, enemy_robot) = (state.get_my_robot(), state.get_enemy_robot());
+      let mut enemy_cost = molecules::Molecules::new();
+      for sample in enemy_robot.get_held_samples().iter().filter(|sample| is_researched(sample)) {
+         enemy_cost = &enemy_cost + &(sample.get_cost() - enemy_robot.get_expertise()).set_minues_to_zero();
+      }
+      let enemy_needs = (&enemy_cost - enemy_robot.get_inventory()).set_minues_to_zero()[molecule] as i32;
+      let our_demand = my_robot
+         .get_held_samples()
+         .iter()
+         .filter(|sample| is_researched(sample) && sample.get_cost()[molecule] as i16 > my_robot.get_expertise()[molecule] as i16)
+         .count() as i32;
+      let available = state.get_available()[molecule] as i32;
+      let mut risk = enemy_needs * Self::ENEMY_NEED_WEIGHT + our_demand * Self::OUR_DEMAND_WEIGHT
+         - available * Self::STOCK_WEIGHT;
+      if enemy_needs > 0 && enemy_needs >= available {
+         risk += Self::CONTESTED_BONUS;
+      }
+      risk
+   }
+
+   // the most at-risk type missing for the best sample we can still complete
+   fn best_molecule(state: &history::TurnState) -> Option<molecules::Molecule> {
+      let (robot, available) = (state.get_my_robot(), state.get_available());
+      let mut held = robot.get_inventory().clone();
+      for sample in Self::sorted_samples(robot) {
+         let cost = sample.get_cost() - robot.get_expertise();
+         let needed = &cost - &held;
+         if needed.is_not_positive() {
+            held = &held - &cost.set_minues_to_zero();
+            continue;
+         }
+         let missing = needed.set_minues_to_zero();
+         if !available.has_enough(&missing) || missing.len() > Self::MAX_MOLECULES - robot.get_inventory().len() {
+            continue;
+         }
+         let mut best: Option<(molecules::Molecule, i32)> = None;
+         for (molecule, _) in missing.iter().filter(|(_, count)| *count > 0) {
+            let risk = Self::pickup_risk(state, molecule);
+            if best.is_none_or(|(_, best_risk)| risk > best_risk) {
+               best = Some((molecule, risk));
+            }
+         }
+         if let Some((molecule, _)) = best {
+            return Some(molecule);
+         }
+      }
+      None
+   }
+
+   fn switch_goal(&mut self, goal: BaselineGoal, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      self.switches += 1;
+      if self.switches > Self::MAX_GOAL_SWITCHES {
+         return Err("goals kept handing over to each other without a command");
+      }
+      self.goal = goal;
+      self.decide_goal(state)
+   }
+
+   fn decide_goal(&mut self, state: &history::TurnState) -> Result<command::Command, &'static str> {
+      let robot = state.get_my_robot();
+      let available = state.get_available();
+      if robot.get_eta() > 0 {
+         return Ok(command::Command::Wait);
+      }
+      match self.goal {
+         BaselineGoal::TakeSamples => {
+            if robot.get_held_samples().len() >= Self::MAX_SAMPLES {
+               return self.switch_goal(BaselineGoal::ResearchSamples, state);
+            }
+            let options = connect_options::ConnectOptions::SampleRank(Self::sample_rank(robot));
+            Ok(connect_at(robot, module::Module::Sample, options))
+         }
+         BaselineGoal::ResearchSamples => match Self::unresearched_sample(robot) {
+            Some(sample) => {
+               let options = connect_options::ConnectOptions::SampleId(sample.get_id());
+               Ok(connect_at(robot, module::Module::Diagnosis, options))
+            }
+            None => self.switch_goal(BaselineGoal::DropSamples, state),
+         },
+         BaselineGoal::GatherMolecules => {
+            let held = robot.get_held_samples();
+            if !held.iter().any(|sample| Self::can_produce(robot, sample, available)) {
+               if held.len() >= Self::MAX_SAMPLES {
+                  return self.switch_goal(BaselineGoal::DropSamples, state);
+               }
+               return self.switch_goal(BaselineGoal::TakeSamples, state);
+            }
+            if robot.get_inventory().len() == Self::MAX_MOLECULES || Self::has_enough_molecules(robot) {
+               return self.switch_goal(BaselineGoal::ProduceMedicine, state);
+            }
+            if robot.get_location() != &module::Module::Molecule {
+               return Ok(command::Command::Goto(module::Module::Molecule));
+            }
+            match Self::best_molecule(state) {
+               Some(molecule) => Ok(command::Command::Connect(connect_options::ConnectOptions::MoleculeType(molecule))),
+               None => self.switch_goal(BaselineGoal::ProduceMedicine, state),
+            }
+         }
+         BaselineGoal::ProduceMedicine => match Self::ready_sample(robot) {
+            Some(sample) => {
+               let options = connect_options::ConnectOptions::SampleId(sample.get_id());
+               Ok(connect_at(robot, module::Module::Laboratory, options))
+            }
+            None if !robot.get_held_samples().is_empty() => self.switch_goal(BaselineGoal::GatherMolecules, state),
+            None => self.switch_goal(BaselineGoal::TakeSamples, state),
+         },
+         BaselineGoal::DropSamples => match Self::impossible_samples(robot, available).first() {
+            Some(sample) => {
+               let options = connect_options::ConnectOptions::SampleId(sample.get_id());
+               Ok(connect_at(robot, module::Module::Diagnosis, options))
+            }
+            None if robot.get_held_samples().len() >= 2 => self.switch_goal(BaselineGoal::GatherMolecules, state),
+            None => self.switch_goal(BaselineGoal::TakeSamples, state),
+         },
+      }
+   }
+}
+
+impl strategy::Strategy for BaselineStrategy {
+   fn name(&self) -> &'static str {
+      Self::NAME
+   }
+
+   fn decide(&mut self, context: &strategy::TurnContext) -> command::Output {
+      self.switches = 0;
+      let decided = self.decide_goal(context.get_state());
+      self.error = decided.as_ref().err().copied();
+      decided.unwrap_or(command::Command::Wait).into()
+   }
+
+   fn get_error(&self) -> Option<&'static str> {
+      self.error
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::history::{History, TurnState};
+   use super::super::module::Module;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::super::strategy::{Strategy, TurnContext};
+   use super::super::tables::LookupTables;
+   use super::super::time_budget::TimeBudget;
+   use super::{BaselineGoal, BaselineStrategy, CloudSniperStrategy, GreedyRankOneStrategy, MoleculeBlockerStrategy, RandomLegalStrategy};
+   use std::time::Instant;
+
+   const PLENTY: &str = "A5 B5 C5 D5 E5";
+
+   fn decide(strategy: &mut dyn Strategy, me: RobotBuilder, enemy: RobotBuilder, cloud: Vec<SampleBuilder>, available: &str) -> String {
+      let cloud = cloud.into_iter().map(|sample| sample.build()).collect();
+      let state = TurnState::new(1, me.build(), enemy.build(), cloud, molecules(available));
+      let (history, tables) = (History::new(), LookupTables::new(Vec::new()));
+      let budget = TimeBudget::for_turn(Instant::now(), 1);
+      strategy.decide(&TurnContext::new(&state, &history, &tables, &budget)).to_string()
+   }
+
+   fn idle_enemy() -> RobotBuilder {
+      RobotBuilder::at(Module::Spawn)
+   }
+
+   #[test]
+   fn random_legal_only_sends_legal_commands() {
+      let mut strategy = RandomLegalStrategy::new(RandomLegalStrategy::DEFAULT_SEED);
+      let me = || RobotBuilder::at(Module::Molecule).inventory("A9").sample(SampleBuilder::new(4).health(10).cost("B2"));
+      for _ in 0..50 {
+         let text = decide(&mut strategy, me(), idle_enemy(), vec![], "A5 C5");
+         let command = Command::parse_at(&text, &Module::Molecule).unwrap();
+         assert!(command.is_legal(&me().build(), &molecules("A5 C5"), &[]), "{}", text);
+      }
+   }
+
+   #[test]
+   fn baseline_keeps_its_own_rules() {
+      let mut strategy = BaselineStrategy::new();
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Spawn), idle_enemy(), vec![], PLENTY), "GOTO SAMPLES");
+      let expert = RobotBuilder::at(Module::Sample).expertise("A2 B2 C2");
+      assert_eq!(decide(&mut strategy, expert, idle_enemy(), vec![], PLENTY), "CONNECT 2");
+
+      // the type the enemy can empty first
+      strategy.goal = BaselineGoal::GatherMolecules;
+      let me = || RobotBuilder::at(Module::Molecule).sample(SampleBuilder::new(3).health(10).cost("A1 D1"));
+      let enemy = RobotBuilder::at(Module::Laboratory).sample(SampleBuilder::new(6).health(20).cost("D3"));
+      assert_eq!(decide(&mut strategy, me(), enemy, vec![], "A5 B5 C5 D2 E5"), "CONNECT D");
+      assert_eq!(decide(&mut strategy, me().inventory("A1 D1"), idle_enemy(), vec![], PLENTY), "GOTO LABORATORY");
+      assert_eq!(strategy.get_error(), None);
+   }
+
+   #[test]
+   fn greedy_rank_one_fills_its_hands_then_works() {
+      let mut strategy = GreedyRankOneStrategy::new();
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Spawn), idle_enemy(), vec![], PLENTY), "GOTO SAMPLES");
+      let one_held = || RobotBuilder::at(Module::Sample).sample(SampleBuilder::new(1));
+      assert_eq!(decide(&mut strategy, one_held(), idle_enemy(), vec![], PLENTY), "CONNECT 1");
+
+      let researched = RobotBuilder::at(Module::Diagnosis)
+         .inventory("A1")
+         .sample(SampleBuilder::new(1).health(1).cost("A1 C1"));
+      assert_eq!(decide(&mut strategy, researched, idle_enemy(), vec![], PLENTY), "GOTO MOLECULES");
+      let gathering = RobotBuilder::at(Module::Molecule)
+         .inventory("A1")
+         .sample(SampleBuilder::new(1).health(1).cost("A1 C1"));
+      assert_eq!(decide(&mut strategy, gathering, idle_enemy(), vec![], PLENTY), "CONNECT C");
+      let done = RobotBuilder::at(Module::Molecule)
+         .inventory("A1 C1")
+         .sample(SampleBuilder::new(1).health(1).cost("A1 C1"));
+      assert_eq!(decide(&mut strategy, done, idle_enemy(), vec![], PLENTY), "GOTO LABORATORY");
+   }
+
+   #[test]
+   fn molecule_blocker_takes_what_the_enemy_needs() {
+      let mut strategy = MoleculeBlockerStrategy::new();
+      let enemy = || RobotBuilder::at(Module::Molecule).sample(SampleBuilder::new(5).health(20).cost("B1 D3"));
+      let me = || RobotBuilder::at(Module::Molecule);
+      assert_eq!(decide(&mut strategy, me(), enemy(), vec![], PLENTY), "CONNECT D");
+      assert_eq!(decide(&mut strategy, me(), enemy(), vec![], "A5 B5 C5 E5"), "CONNECT B");
+      assert_eq!(decide(&mut strategy, me(), idle_enemy(), vec![], "A5 B2 C5 D5 E5"), "CONNECT B");
+      assert_eq!(decide(&mut strategy, me().inventory("A10"), enemy(), vec![], PLENTY), "WAIT");
+   }
+
+   #[test]
+   fn cloud_sniper_waits_for_dropped_samples() {
+      let mut strategy = CloudSniperStrategy::new();
+      let diagnosis = || RobotBuilder::at(Module::Diagnosis);
+      assert_eq!(decide(&mut strategy, RobotBuilder::at(Module::Spawn), idle_enemy(), vec![], PLENTY), "GOTO DIAGNOSIS");
+      assert_eq!(decide(&mut strategy, diagnosis(), idle_enemy(), vec![], PLENTY), "WAIT");
+
+      let cloud = || vec![SampleBuilder::new(7).health(10).cost("B2"), SampleBuilder::new(8)];
+      assert_eq!(decide(&mut strategy, diagnosis(), idle_enemy(), cloud(), PLENTY), "CONNECT 7");
+      let sniped = diagnosis().sample(SampleBuilder::new(7).health(10).cost("B2"));
+      assert_eq!(decide(&mut strategy, sniped, idle_enemy(), vec![SampleBuilder::new(8)], PLENTY), "GOTO MOLECULES");
+   }
+}
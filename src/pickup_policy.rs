@@ -0,0 +1,133 @@
+use super::molecules;
+use super::robot;
+
+/**
+ * Decides which molecule type to grab first when several are missing, for
+ * `Molecules::get_next_molecule`.
+ * A type is more at risk the less of it is left in stock, the more the enemy
+ * still needs of it and the more of our samples depend on it.
+ */
+#[derive(Debug)]
+pub struct PickupPolicy {
+   available: molecules::Molecules,
+   enemy_needs: molecules::Molecules,
+   our_demand: molecules::Molecules,
+}
+
+impl PickupPolicy {
+   const ENEMY_NEED_WEIGHT: i32 = 3;
+   const OUR_DEMAND_WEIGHT: i32 = 2;
+   const STOCK_WEIGHT: i32 = 2;
+   // the enemy alone can empty the stock of this type
+   const CONTESTED_BONUS: i32 = 20;
+
+   pub fn new(
+      available: molecules::Molecules,
+      enemy_needs: molecules::Molecules,
+      our_demand: molecules::Molecules,
+   ) -> Self {
+      Self {
+         available,
+         enemy_needs,
+         our_demand,
+      }
+   }
+
+   // every type scores the same, so types are picked in A to E order
+   pub fn fixed_order() -> Self {
+      Self::new(
+         molecules::Molecules::new(),
+         molecules::Molecules::new(),
+         molecules::Molecules::new(),
+      )
+   }
+
+   pub fn for_robots(
+      my_robot: &robot::Robot,
+      enemy_robot: &robot::Robot,
+      available: &molecules::Molecules,
+   ) -> Self {
+      Self::new(
+         available.clone(),
+         enemy_robot.get_outstanding_needs(),
+         my_robot.count_samples_needing(),
+      )
+   }
+
+   pub fn risk(&self, molecule: molecules::Molecule) -> i32 {
+      let available = self.available[molecule] as i32;
+      let enemy_needs = self.enemy_needs[molecule] as i32;
+      let mut risk = enemy_needs * Self::ENEMY_NEED_WEIGHT
+         + self.our_demand[molecule] as i32 * Self::OUR_DEMAND_WEIGHT
+         - available * Self::STOCK_WEIGHT;
+      if enemy_needs > 0 && enemy_needs >= available {
+         risk += Self::CONTESTED_BONUS;
+      }
+      risk
+   }
+
+   // most at-risk type among the positive entries of `needed`, earliest type wins ties
+   pub fn pick(&self, needed: &molecules::Molecules) -> Option<molecules::Molecule> {
+      let mut best: Option<(molecules::Molecule, i32)> = None;
+      for (molecule, count) in needed.iter() {
+         if count <= 0 {
+            continue;
+         }
+         let risk = self.risk(molecule);
+         match best {
+            Some((_, best_risk)) if best_risk >= risk => {}
+            _ => best = Some((molecule, risk)),
+         }
+      }
+      best.map(|(molecule, _)| molecule)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::module::Module;
+   use super::super::molecules::Molecule;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::PickupPolicy;
+
+   #[test]
+   fn fixed_order_picks_the_first_positive_type() {
+      let needed = molecules("A-1 B0 C2 E1");
+      assert_eq!(needed.get_next_molecule(&PickupPolicy::fixed_order()), Some(Molecule::C));
+      assert_eq!(molecules("A-1").get_next_molecule(&PickupPolicy::fixed_order()), None);
+   }
+
+   #[test]
+   fn scarce_type_comes_first() {
+      let policy = PickupPolicy::new(molecules("A5 B5 C5 D5 E1"), molecules(""), molecules(""));
+      assert_eq!(policy.pick(&molecules("A1 E1")), Some(Molecule::E));
+   }
+
+   #[test]
+   fn type_the_enemy_needs_comes_first() {
+      let policy = PickupPolicy::new(molecules("A3 B3 C3 D3 E3"), molecules("C3"), molecules(""));
+      assert_eq!(policy.pick(&molecules("A1 B1 C1")), Some(Molecule::C));
+   }
+
+   #[test]
+   fn type_shared_by_our_samples_comes_first() {
+      let policy = PickupPolicy::new(molecules("A5 B5 C5 D5 E5"), molecules(""), molecules("A1 D3"));
+      assert_eq!(policy.pick(&molecules("A1 D1")), Some(Molecule::D));
+   }
+
+   #[test]
+   fn for_robots_reads_both_robots() {
+      let me = RobotBuilder::at(Module::Molecule)
+         .sample(SampleBuilder::new(1).health(10).cost("A1 E1"))
+         .sample(SampleBuilder::new(2).health(10).cost("E2"))
+         .build();
+      let enemy = RobotBuilder::at(Module::Molecule)
+         .inventory("B1")
+         .expertise("B1")
+         .sample(SampleBuilder::new(3).health(10).cost("B4"))
+         .build();
+      let policy = PickupPolicy::for_robots(&me, &enemy, &molecules("A5 B2 C5 D5 E5"));
+      assert_eq!(policy.pick(&molecules("A1 B1 E1")), Some(Molecule::B));
+      assert_eq!(policy.pick(&molecules("A1 E1")), Some(Molecule::E));
+   }
+}
@@ -0,0 +1,188 @@
+/*!
+ * Reproducible random numbers without pulling a crate: xoshiro256** seeded
+ * through splitmix64. Everything random in the crate draws from a `Random`
+ * built from one seed, and components that need their own sequence take a
+ * `split` of it rather than a seed of their own.
+ */
+
+#[derive(Clone, Debug)]
+pub struct Random {
+   state: [u64; 4],
+}
+
+/**
+ * The splitmix64 finalizer: a bijection on u64 that spreads every input bit
+ * over the whole output, for seeding and for keys derived from small values.
+ */
+pub fn mix(value: u64) -> u64 {
+   let mut z = value;
+   z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+   z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+   z ^ (z >> 31)
+}
+
+impl Random {
+   // advances the generator by 2^128 draws, see `split`
+   const JUMP: [u64; 4] = [0x180E_C6D3_3CFD_0ABA, 0xD5A6_1266_F0C9_392C, 0xA958_2618_E03F_C9AA, 0x39AB_DC45_29B1_661C];
+
+   pub fn new(seed: u64) -> Self {
+      // splitmix64 spreads any seed, zero included, over the whole state
+      let mut seed = seed;
+      let mut state = [0; 4];
+      for word in state.iter_mut() {
+         seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+         *word = mix(seed);
+      }
+      Self { state }
+   }
+
+   pub fn next_u64(&mut self) -> u64 {
+      let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+      let shifted = self.state[1] << 17;
+      self.state[2] ^= self.state[0];
+      self.state[3] ^= self.state[1];
+      self.state[1] ^= self.state[2];
+      self.state[0] ^= self.state[3];
+      self.state[2] ^= shifted;
+      self.state[3] = self.state[3].rotate_left(45);
+      result
+   }
+
+   // uniform in 0..bound without modulo bias, `bound` must not be 0
+   pub fn below(&mut self, bound: usize) -> usize {
+      assert!(bound > 0, "empty range");
+      let bound = bound as u64;
+      let threshold = bound.wrapping_neg() % bound;
+      loop {
+         let product = self.next_u64() as u128 * bound as u128;
+         if product as u64 >= threshold {
+            return (product >> 64) as usize;
+         }
+      }
+   }
+
+   // uniform in low..=high
+   pub fn between(&mut self, low: i64, high: i64) -> i64 {
+      let span = (high as i128 - low as i128 + 1) as u128;
+      if span > u64::MAX as u128 {
+         return self.next_u64() as i64;
+      }
+      (low as i128 + self.below(span as usize) as i128) as i64
+   }
+
+   // true `numerator` times out of `denominator`
+   pub fn chance(&mut self, numerator: usize, denominator: usize) -> bool {
+      self.below(denominator) < numerator
+   }
+
+   pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+      if items.is_empty() {
+         return None;
+      }
+      Some(&items[self.below(items.len())])
+   }
+
+   // an index drawn in proportion to its weight, None when they are all 0
+   pub fn choose_weighted(&mut self, weights: &[u32]) -> Option<usize> {
+      let total = weights.iter().map(|weight| *weight as u64).sum::<u64>();
+      if total == 0 {
+         return None;
+      }
+      let mut left = self.below(total as usize) as u64;
+      for (index, weight) in weights.iter().enumerate() {
+         if left < *weight as u64 {
+            return Some(index);
+         }
+         left -= *weight as u64;
+      }
+      unreachable!("the draw is below the total weight")
+   }
+
+   // Fisher-Yates
+   pub fn shuffle<T>(&mut self, items: &mut [T]) {
+      for index in (1..items.len()).rev() {
+         let other = self.below(index + 1);
+         items.swap(index, other);
+      }
+   }
+
+   /**
+    * A new generator for another component. It continues from the current
+    * state while this one jumps 2^128 draws ahead, so the two never overlap
+    * and the result only depends on the original seed and the call order.
+    */
+   pub fn split(&mut self) -> Random {
+      let child = self.clone();
+      let mut jumped = [0; 4];
+      for word in Self::JUMP {
+         for bit in 0..64 {
+            if word & (1 << bit) != 0 {
+               for (target, source) in jumped.iter_mut().zip(self.state.iter()) {
+                  *target ^= source;
+               }
+            }
+            self.next_u64();
+         }
+      }
+      self.state = jumped;
+      child
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::Random;
+
+   #[test]
+   fn same_seed_same_sequence() {
+      let (mut first, mut second) = (Random::new(42), Random::new(42));
+      let draws = (0..100).map(|_| first.next_u64()).collect::<Vec<_>>();
+      assert_eq!(draws, (0..100).map(|_| second.next_u64()).collect::<Vec<_>>());
+      assert_ne!(draws, (0..100).map(|_| Random::new(43).next_u64()).collect::<Vec<_>>());
+      assert_ne!(Random::new(0).next_u64(), 0);
+   }
+
+   #[test]
+   fn draws_stay_in_range_and_cover_it() {
+      let mut random = Random::new(1);
+      let mut seen = [0; 7];
+      for _ in 0..7000 {
+         seen[random.below(7)] += 1;
+         let value = random.between(-3, 3);
+         assert!((-3..=3).contains(&value));
+      }
+      assert!(seen.iter().all(|count| (800..1200).contains(count)));
+      assert_eq!(random.between(5, 5), 5);
+      random.between(i64::MIN, i64::MAX);
+      assert!(!random.chance(0, 3) && random.chance(3, 3));
+   }
+
+   #[test]
+   fn shuffles_and_chooses() {
+      let mut random = Random::new(2);
+      let mut items = (0..20).collect::<Vec<_>>();
+      random.shuffle(&mut items);
+      assert_ne!(items, (0..20).collect::<Vec<_>>());
+      items.sort();
+      assert_eq!(items, (0..20).collect::<Vec<_>>());
+
+      assert_eq!(random.choose::<u8>(&[]), None);
+      assert_eq!(random.choose_weighted(&[0, 0]), None);
+      let mut picks = [0; 3];
+      for _ in 0..4000 {
+         picks[random.choose_weighted(&[1, 0, 3]).unwrap()] += 1;
+      }
+      assert_eq!(picks[1], 0);
+      assert!((2700..3300).contains(&picks[2]));
+   }
+
+   #[test]
+   fn split_streams_are_independent_and_reproducible() {
+      let (mut parent, mut again) = (Random::new(3), Random::new(3));
+      let mut child = parent.split();
+      let mut child_again = again.split();
+      let draws = (0..50).map(|_| child.next_u64()).collect::<Vec<_>>();
+      assert_eq!(draws, (0..50).map(|_| child_again.next_u64()).collect::<Vec<_>>());
+      assert_ne!(draws, (0..50).map(|_| parent.next_u64()).collect::<Vec<_>>());
+   }
+}
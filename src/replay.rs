@@ -0,0 +1,271 @@
+/*!
+ * Recorded games and their rendering, for looking at a game after the fact.
+ *
+ * A recording is what our bot read on stdin, projects first and then one
+ * block per turn, optionally paired with what it printed, one command per
+ * line. Whatever was not recorded is inferred from consecutive turns.
+ */
+
+use super::carried_by;
+use super::command;
+use super::history;
+use super::input_reading;
+use super::molecules;
+use super::robot;
+use super::sample;
+use super::turn_diff;
+use std::fmt::Write;
+use std::io::BufRead;
+
+#[derive(Debug)]
+pub struct Replay {
+   projects: Vec<molecules::Molecules>,
+   states: Vec<history::TurnState>,
+   my_commands: Vec<Option<command::Output>>,
+}
+
+// true once only whitespace is left
+fn at_end<R: BufRead>(reader: &mut R) -> bool {
+   loop {
+      let buffer = match reader.fill_buf() {
+         Ok(buffer) => buffer,
+         Err(_) => return true,
+      };
+      if buffer.is_empty() {
+         return true;
+      }
+      let blank = buffer.iter().take_while(|byte| byte.is_ascii_whitespace()).count();
+      if blank < buffer.len() {
+         return false;
+      }
+      reader.consume(blank);
+   }
+}
+
+impl Replay {
+   pub fn new(projects: Vec<molecules::Molecules>) -> Self {
+      Self {
+         projects,
+         states: Vec::new(),
+         my_commands: Vec::new(),
+      }
+   }
+
+   pub fn read_from<R: BufRead>(input: &mut R) -> Self {
+      let mut replay = Self::new(input_reading::parse_projects_from(input));
+      while !at_end(input) {
+         let (my_robot, enemy_robot, cloud, available, _) = input_reading::parse_turn_input_from(input);
+         let turn = replay.states.len() as u16;
+         replay.push(history::TurnState::new(turn, my_robot, enemy_robot, cloud, available), None);
+      }
+      replay
+   }
+
+   // our commands as printed, in the same order as the turns
+   pub fn read_commands_from<R: BufRead>(&mut self, output: &mut R) {
+      for (turn, line) in output.lines().map_while(Result::ok).enumerate() {
+         if turn >= self.states.len() {
+            break;
+         }
+         let location = self.states[turn].get_my_robot().get_location();
+         self.my_commands[turn] = command::Output::parse_at(&line, location).ok();
+      }
+   }
+
+   pub fn push(&mut self, state: history::TurnState, my_command: Option<command::Output>) {
+      self.states.push(state);
+      self.my_commands.push(my_command);
+   }
+
+   pub fn get_projects(&self) -> &Vec<molecules::Molecules> {
+      &self.projects
+   }
+   pub fn get_states(&self) -> &Vec<history::TurnState> {
+      &self.states
+   }
+   // only what was recorded, see `commands` for a guess otherwise
+   pub fn get_my_command(&self, turn: usize) -> Option<&command::Output> {
+      self.my_commands.get(turn).and_then(|command| command.as_ref())
+   }
+   pub fn len(&self) -> usize {
+      self.states.len()
+   }
+   pub fn is_empty(&self) -> bool {
+      self.states.is_empty()
+   }
+
+   /**
+    * Commands sent on `turn` by us and by the enemy: recorded ones as they
+    * are, the others rebuilt from the next turn. None for the last turn.
+    */
+   pub fn commands(&self, turn: usize) -> (Option<command::Output>, Option<command::Output>) {
+      let next = match self.states.get(turn + 1) {
+         Some(next) => next,
+         None => return (self.my_commands[turn].clone(), None),
+      };
+      let current = &self.states[turn];
+      let mine = self.my_commands[turn]
+         .clone()
+         .unwrap_or_else(|| turn_diff::infer_command(current, next, carried_by::CarriedBy::Me).into());
+      (Some(mine), Some(turn_diff::infer_command(current, next, carried_by::CarriedBy::Other).into()))
+   }
+
+   fn describe_robot(text: &mut String, title: &str, robot: &robot::Robot, command: Option<&command::Output>) {
+      let _ = writeln!(
+         text,
+         "{:<6} {} eta {}  score {}  inventory {}  expertise {}",
+         title,
+         robot.get_location().as_str(),
+         robot.get_eta(),
+         robot.get_score(),
+         robot.get_inventory(),
+         robot.get_expertise()
+      );
+      if let Some(command) = command {
+         let _ = writeln!(text, "       > {}", command);
+      }
+      for sample in robot.get_held_samples() {
+         let _ = writeln!(text, "       {}", Self::describe_sample(sample));
+      }
+   }
+
+   fn describe_sample(sample: &sample::Sample) -> String {
+      let gain = sample.get_expertise_gain();
+      match sample.get_health() {
+         sample::SampleHealth::Researched(health) => format!(
+            "s{} r{} {}hp cost {} gives {}",
+            sample.get_id(),
+            sample.get_rank().as_value(),
+            health,
+            sample.get_cost(),
+            gain
+         ),
+         sample::SampleHealth::Unresearched => {
+            format!("s{} r{} undiagnosed gives {}", sample.get_id(), sample.get_rank().as_value(), gain)
+         }
+      }
+   }
+
+   // one turn as plain text, the same for the terminal and the HTML page
+   pub fn describe_turn(&self, turn: usize) -> String {
+      let state = &self.states[turn];
+      let (mine, theirs) = self.commands(turn);
+      let mut text = format!("turn {} of {}\n", turn + 1, self.states.len());
+      let projects = self.projects.iter().map(|project| project.to_string()).collect::<Vec<_>>();
+      let _ = writeln!(text, "projects  {}", projects.join(" | "));
+      let _ = writeln!(text, "available {}", state.get_available());
+      Self::describe_robot(&mut text, "me", state.get_my_robot(), mine.as_ref());
+      Self::describe_robot(&mut text, "enemy", state.get_enemy_robot(), theirs.as_ref());
+      let _ = writeln!(text, "cloud");
+      for sample in state.get_cloud() {
+         let _ = writeln!(text, "       {}", Self::describe_sample(sample));
+      }
+      text
+   }
+
+   // screen clearing and colours for a terminal; pipe through `less -R` to scroll
+   pub fn render_ansi(&self, turn: usize) -> String {
+      const CLEAR: &str = "\x1b[2J\x1b[H";
+      const RESET: &str = "\x1b[0m";
+      let mut frame = String::from(CLEAR);
+      for line in self.describe_turn(turn).lines() {
+         let colour = match line.split_whitespace().next() {
+            Some("me") => "\x1b[1;32m",
+            Some("enemy") => "\x1b[1;31m",
+            Some(">") => "\x1b[33m",
+            Some("turn") => "\x1b[1m",
+            _ => "",
+         };
+         let _ = writeln!(frame, "{}{}{}", colour, line, RESET);
+      }
+      frame
+   }
+
+   // a single page without external assets, arrow keys or the slider step through the turns
+   pub fn render_html(&self) -> String {
+      let mut page = String::from(
+         "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Code4Life replay</title>\n\
+          <style>body{font-family:monospace;background:#111;color:#ddd}pre{font-size:14px}\
+          .turn{display:none}.turn.shown{display:block}input{width:100%}</style></head><body>\n",
+      );
+      let _ = writeln!(
+         page,
+         "<input id=\"slider\" type=\"range\" min=\"0\" max=\"{}\" value=\"0\">",
+         self.states.len().saturating_sub(1)
+      );
+      for turn in 0..self.states.len() {
+         let _ = writeln!(page, "<pre class=\"turn\" id=\"turn{}\">{}</pre>", turn, escape_html(&self.describe_turn(turn)));
+      }
+      page.push_str(
+         "<script>\n\
+          const slider = document.getElementById('slider');\n\
+          function show(turn) {\n\
+          \x20 document.querySelectorAll('.turn.shown').forEach(element => element.classList.remove('shown'));\n\
+          \x20 const element = document.getElementById('turn' + turn);\n\
+          \x20 if (element) { element.classList.add('shown'); slider.value = turn; }\n\
+          }\n\
+          slider.addEventListener('input', () => show(slider.value));\n\
+          document.addEventListener('keydown', event => {\n\
+          \x20 if (event.key === 'ArrowRight') show(Math.min(+slider.value + 1, +slider.max));\n\
+          \x20 if (event.key === 'ArrowLeft') show(Math.max(+slider.value - 1, 0));\n\
+          });\n\
+          show(0);\n\
+          </script>\n</body></html>\n",
+      );
+      page
+   }
+}
+
+fn escape_html(text: &str) -> String {
+   text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::module::Module;
+   use super::Replay;
+   use std::io::Cursor;
+
+   const RECORDING: &str = "1\n\
+      0 3 0 3 3\n\
+      SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+      SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+      5 5 5 5 5\n\
+      0\n\
+      SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+      SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+      5 5 5 5 5\n\
+      2\n\
+      0 0 1 A -1 -1 -1 -1 -1 -1\n\
+      1 1 1 B -1 -1 -1 -1 -1 -1\n\
+      \n";
+
+   #[test]
+   fn reads_a_recording_and_fills_in_commands() {
+      let mut replay = Replay::read_from(&mut Cursor::new(RECORDING));
+      assert_eq!(replay.len(), 2);
+      assert_eq!(replay.get_projects().len(), 1);
+      let (mine, theirs) = replay.commands(0);
+      assert_eq!(mine.map(|command| command.to_string()), Some("CONNECT 1".to_owned()));
+      assert_eq!(theirs.map(|command| command.to_string()), Some("CONNECT 1".to_owned()));
+      assert_eq!(replay.commands(1), (None, None));
+
+      replay.read_commands_from(&mut Cursor::new("CONNECT 1 TAKE r1\nGOTO DIAGNOSIS\n"));
+      let (mine, _) = replay.commands(1);
+      assert_eq!(mine, Some(Command::Goto(Module::Diagnosis).into()));
+      assert!(replay.describe_turn(0).contains("> CONNECT 1 TAKE r1"));
+   }
+
+   #[test]
+   fn html_page_is_self_contained() {
+      let mut replay = Replay::read_from(&mut Cursor::new(RECORDING));
+      replay.read_commands_from(&mut Cursor::new("WAIT <b>&\n"));
+      let page = replay.render_html();
+      assert!(page.contains("id=\"turn1\""));
+      assert!(page.contains("WAIT &lt;b&gt;&amp;"));
+      assert!(!page.contains("http"));
+      assert!(!page.contains("src="));
+      assert!(replay.render_ansi(1).contains("s1 r1 undiagnosed gives B"));
+   }
+}
@@ -1,8 +1,9 @@
 use super::module;
 use super::molecules;
+use super::pickup_policy;
 use super::sample;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Robot {
     location: module::Module,
     eta: u8,
@@ -12,9 +13,15 @@ pub struct Robot {
     held_samples: Vec<sample::Sample>,
 }
 
+impl Default for Robot {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
 impl Robot {
-   const EXPERTISE_UNTIL_MIDDLE_RANK: i8 = 3;
-   const EXPERTISE_UNTIL_HIGH_RANK: i8 = 9;
+   const EXPERTISE_UNTIL_MIDDLE_RANK: i16 = 3;
+   const EXPERTISE_UNTIL_HIGH_RANK: i16 = 9;
    pub fn get_held_samples(&self) -> &Vec<sample::Sample> {
       &self.held_samples
    }
@@ -27,6 +34,15 @@ impl Robot {
    pub fn get_location(&self) -> &module::Module {
       &self.location
    }
+   pub fn get_score(&self) -> i16 {
+      self.score
+   }
+   pub fn get_inventory(&self) -> &molecules::Molecules {
+      &self.inventory
+   }
+   pub fn get_expertise(&self) -> &molecules::Molecules {
+      &self.expertise
+   }
    pub fn new() -> Self {
       Self {
          location: module::Module::Spawn,
@@ -38,11 +54,29 @@ impl Robot {
       }
    }
 
+   pub fn from_parts(
+      location: module::Module,
+      eta: u8,
+      score: i16,
+      inventory: molecules::Molecules,
+      expertise: molecules::Molecules,
+      held_samples: Vec<sample::Sample>,
+   ) -> Self {
+      Self {
+         location,
+         eta,
+         score,
+         inventory,
+         expertise,
+         held_samples,
+      }
+   }
+
    const MAX_SAMPLES: usize = 3;
-   const MAX_MOLECULES: i8 = 10;
+   const MAX_MOLECULES: i16 = 10;
    pub fn new_from_inputs(inputs: Vec<&str>) -> Self {
       Self {
-         location: module::Module::from_str(inputs[0].trim()).unwrap(),
+         location: inputs[0].trim().parse::<module::Module>().unwrap(),
          eta: parse_input!(inputs[1], u8),
          score: parse_input!(inputs[2], i16),
          inventory: molecules::Molecules::from_slice(&inputs[3..8]),
@@ -55,13 +89,10 @@ impl Robot {
       let filtered_values = self
          .held_samples
          .iter()
-         .filter(|sample| match sample.get_health() {
-            sample::SampleHealth::Unresearched => true,
-            _ => false,
-         })
+         .filter(|sample| matches!(sample.get_health(), sample::SampleHealth::Unresearched))
          .collect::<Vec<_>>();
 
-      if filtered_values.len() > 0 {
+      if !filtered_values.is_empty() {
          Some(filtered_values[0])
       } else {
          None
@@ -78,19 +109,23 @@ impl Robot {
             continue;
          }
       }
-      return None;
+      None
    }
 
    pub fn has_maximum_samples(&self) -> bool {
-      return self.held_samples.len() >= Self::MAX_SAMPLES;
+      self.held_samples.len() >= Self::MAX_SAMPLES
    }
 
    pub fn has_maximum_molecules(&self) -> bool {
-      return self.inventory.len() == Self::MAX_MOLECULES;
+      self.inventory.len() == Self::MAX_MOLECULES
    }
 
    // TODO: inspect for similarities with has_enough_molecules and refactor
-   pub fn pick_best_molecule(&self, available: &molecules::Molecules, ) -> Option<molecules::Molecule> {
+   pub fn pick_best_molecule(
+      &self,
+      available: &molecules::Molecules,
+      policy: &pickup_policy::PickupPolicy,
+   ) -> Option<molecules::Molecule> {
       let sorted_samples: Vec<&sample::Sample> = self.get_sorted_samples();
       // go through every sample
       let mut held_molecules = self.inventory.clone();
@@ -109,25 +144,30 @@ impl Robot {
          if needed_molecules.set_minues_to_zero().len() > (Self::MAX_MOLECULES - self.inventory.len()) {
             continue;
          }
-         if let Some(found_molecule) = needed_molecules.get_next_molecule() {
+         if let Some(found_molecule) = needed_molecules.get_next_molecule(policy) {
             return Some(found_molecule);
          } else {
             continue;
          }
       }
-      return None;
+      None
    }
 
    pub fn get_sorted_samples(&self) -> Vec<&sample::Sample> {
       let mut sorted_samples: Vec<&sample::Sample> =
-         self.held_samples.iter().map(|sample| sample).collect();
+         self.held_samples.iter().collect();
       sorted_samples.sort_by_key(|&sample| match sample.get_health() {
          sample::SampleHealth::Researched(health) => -(*health as i8),
          _ => 0i8,
       });
-      return sorted_samples;
+      sorted_samples
    }
 
+   /**
+    * Whether the inventory covers every held sample, best first. Each sample
+    * produced uses up its cost minus the expertise at that point, which is
+    * no longer available to the samples after it.
+    */
    pub fn has_enough_molecules(&self) -> bool {
       let sorted_samples: Vec<&sample::Sample> = self.get_sorted_samples();
       let mut held_molecules = self.inventory.clone();
@@ -136,13 +176,14 @@ impl Robot {
          // copied from get_most_interesting_ready_sample
          let needed_molecules = sample.get_cost() - &(&accumulated_expertise + &held_molecules);
          if needed_molecules.is_not_positive() {
+            let spent_molecules = (sample.get_cost() - &accumulated_expertise).set_minues_to_zero();
             accumulated_expertise = &accumulated_expertise + sample.get_expertise_gain();
-            held_molecules = &held_molecules - &needed_molecules.set_minues_to_zero();
+            held_molecules = &held_molecules - &spent_molecules;
          } else {
             return false;
          }
       }
-      return true;
+      true
    }
 
    pub fn can_produce_one_held_sample(&self, available: &molecules::Molecules) -> bool {
@@ -180,7 +221,418 @@ impl Robot {
          .collect::<Vec<_>>()
    }
 
+   // molecules still missing for every researched held sample together
+   pub fn get_outstanding_needs(&self) -> molecules::Molecules {
+      let mut total_cost = molecules::Molecules::new();
+      for sample in self.held_samples.iter() {
+         if let sample::SampleHealth::Researched(_) = sample.get_health() {
+            total_cost = &total_cost + &(sample.get_cost() - &self.expertise).set_minues_to_zero();
+         }
+      }
+      (&total_cost - &self.inventory).set_minues_to_zero()
+   }
+
+   // number of researched held samples that still cost each molecule type
+   pub fn count_samples_needing(&self) -> molecules::Molecules {
+      let mut counts = molecules::Molecules::new();
+      for sample in self.held_samples.iter() {
+         if let sample::SampleHealth::Researched(_) = sample.get_health() {
+            for (molecule, count) in (sample.get_cost() - &self.expertise).iter() {
+               if count > 0 {
+                  counts[molecule] += 1;
+               }
+            }
+         }
+      }
+      counts
+   }
+
+   // room left once the molecules still missing for held samples are counted in
+   pub fn get_free_capacity(&self) -> i16 {
+      Self::MAX_MOLECULES - self.inventory.len() - self.get_outstanding_needs().len()
+   }
+
+   // held molecules not earmarked for any held sample
+   pub fn get_spare_molecules(&self) -> molecules::Molecules {
+      let mut earmarked = molecules::Molecules::new();
+      for sample in self.held_samples.iter() {
+         if let sample::SampleHealth::Researched(_) = sample.get_health() {
+            earmarked = &earmarked + &(sample.get_cost() - &self.expertise).set_minues_to_zero();
+         }
+      }
+      (&self.inventory - &earmarked).set_minues_to_zero()
+   }
+
+   /**
+    * Fills spare capacity ahead of time: first with what the `planned` samples
+    * (e.g. cloud samples we mean to take) are missing, then with the types a
+    * sample of the next rank we would draw statistically costs beyond our expertise.
+    */
+   pub fn pick_speculative_molecule(
+      &self,
+      available: &molecules::Molecules,
+      policy: &pickup_policy::PickupPolicy,
+      planned: &[&sample::Sample],
+   ) -> Option<molecules::Molecule> {
+      if self.get_free_capacity() <= 0 {
+         return None;
+      }
+      let spare = self.get_spare_molecules();
+      for sample in planned {
+         let cost = (sample.get_cost() - &self.expertise).set_minues_to_zero();
+         let missing = (&cost - &spare).set_minues_to_zero();
+         if let Some(found_molecule) = missing.min(available).get_next_molecule(policy) {
+            return Some(found_molecule);
+         }
+      }
+      let expected_cost = self.pick_sample_based_on_expertise().expected_cost_per_type();
+      let expected = molecules::Molecules::from_array([expected_cost; 5]);
+      let gaps = (&(&expected - &self.expertise) - &spare).set_minues_to_zero();
+      gaps.min(available).get_next_molecule(policy)
+   }
+
+   // researched cloud samples we could fit in our free sample slots and produce
+   pub fn pick_cloud_samples<'a>(
+      &self,
+      cloud: &'a [sample::Sample],
+      available: &molecules::Molecules,
+   ) -> Vec<&'a sample::Sample> {
+      let free_slots = Self::MAX_SAMPLES.saturating_sub(self.held_samples.len());
+      let mut candidates = cloud
+         .iter()
+         .filter(|sample| matches!(sample.get_health(), sample::SampleHealth::Researched(_)))
+         .filter(|sample| self.can_produce_sample(sample, available))
+         .collect::<Vec<_>>();
+      candidates.sort_by_key(|sample| match sample.get_health() {
+         sample::SampleHealth::Researched(health) => -(*health as i16),
+         _ => 0,
+      });
+      candidates.truncate(free_slots);
+      candidates
+   }
+
    pub fn has_enough_samples(&self) -> bool {
       self.held_samples.len() >= 2
    }
+
+   // the referee moves robots towards their target one step per turn
+   pub fn start_moving(&mut self, target: module::Module, eta: u8) {
+      self.location = target;
+      self.eta = eta;
+   }
+
+   pub fn advance(&mut self) {
+      self.eta = self.eta.saturating_sub(1);
+   }
+
+   pub fn remove_sample(&mut self, id: u8) -> Option<sample::Sample> {
+      let position = self.held_samples.iter().position(|sample| sample.get_id() == id)?;
+      Some(self.held_samples.remove(position))
+   }
+
+   pub fn take_molecule(&mut self, molecule: molecules::Molecule) {
+      self.inventory[molecule] += 1;
+   }
+
+   // spends what expertise does not cover, scores the sample and returns the spent molecules
+   pub fn produce(&mut self, sample: &sample::Sample) -> molecules::Molecules {
+      let spent = (sample.get_cost() - &self.expertise).set_minues_to_zero();
+      self.inventory = &self.inventory - &spent;
+      self.expertise = &self.expertise + sample.get_expertise_gain();
+      if let sample::SampleHealth::Researched(health) = sample.get_health() {
+         self.score += *health as i16;
+      }
+      spent
+   }
+
+   pub fn add_score(&mut self, points: i16) {
+      self.score += points;
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::module::Module;
+   use super::super::molecules::Molecules;
+   use super::super::pickup_policy::PickupPolicy;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::Robot;
+
+   const PLENTY: &str = "A5 B5 C5 D5 E5";
+
+   struct Scenario {
+      name: &'static str,
+      robot: Robot,
+      available: Molecules,
+   }
+
+   fn scenario(name: &'static str, robot: RobotBuilder, available: &str) -> Scenario {
+      Scenario {
+         name,
+         robot: robot.build(),
+         available: molecules(available),
+      }
+   }
+
+   fn at_molecules() -> RobotBuilder {
+      RobotBuilder::at(Module::Molecule)
+   }
+
+   fn sample(id: u8, health: u8, cost: &str) -> SampleBuilder {
+      SampleBuilder::new(id).health(health).cost(cost).gain('A')
+   }
+
+   #[test]
+   fn pick_best_molecule_scenarios() {
+      let table = vec![
+         (
+            scenario("empty inventory takes first missing type", at_molecules().sample(sample(1, 10, "A2 B1")), PLENTY),
+            Some('A'),
+         ),
+         (
+            scenario(
+               "held molecules are not picked again",
+               at_molecules().inventory("A2").sample(sample(1, 10, "A2 B1")),
+               PLENTY,
+            ),
+            Some('B'),
+         ),
+         (
+            scenario(
+               "expertise covers part of the cost",
+               at_molecules().expertise("A2").sample(sample(1, 10, "A2 B1")),
+               PLENTY,
+            ),
+            Some('B'),
+         ),
+         (
+            scenario(
+               "expertise exceeds the cost",
+               at_molecules().expertise("A5").sample(sample(1, 10, "A2 B1")),
+               PLENTY,
+            ),
+            Some('B'),
+         ),
+         (
+            scenario(
+               "expertise covers the whole cost",
+               at_molecules().expertise("A3 B3").sample(sample(1, 10, "A2 B1")),
+               PLENTY,
+            ),
+            None,
+         ),
+         (
+            scenario("missing stock skips the sample", at_molecules().sample(sample(1, 10, "A2 B1")), "A5"),
+            None,
+         ),
+         (
+            scenario(
+               "sample that would exceed the 10 molecule cap is skipped",
+               at_molecules().inventory("C5 D4").sample(sample(1, 10, "A2")),
+               PLENTY,
+            ),
+            None,
+         ),
+         (
+            scenario(
+               "sample that exactly fills the 10 molecule cap is kept",
+               at_molecules().inventory("C5 D4").sample(sample(1, 10, "A1")),
+               PLENTY,
+            ),
+            Some('A'),
+         ),
+         (
+            scenario(
+               "healthier sample is served first",
+               at_molecules().sample(sample(1, 10, "A1")).sample(sample(2, 30, "B1")),
+               PLENTY,
+            ),
+            Some('B'),
+         ),
+         (
+            scenario(
+               "completed sample keeps its molecules",
+               at_molecules().inventory("A1").sample(sample(1, 30, "A1")).sample(sample(2, 10, "A1")),
+               PLENTY,
+            ),
+            Some('A'),
+         ),
+         (
+            scenario(
+               "infeasible sample falls through to the next one",
+               at_molecules().sample(sample(1, 30, "E3")).sample(sample(2, 10, "C1")),
+               "C1 E2",
+            ),
+            Some('C'),
+         ),
+      ];
+      for (scenario, expected) in table {
+         let picked = scenario.robot.pick_best_molecule(&scenario.available, &PickupPolicy::fixed_order());
+         assert_eq!(picked.map(|molecule| molecule.as_char()), expected, "{}", scenario.name);
+      }
+   }
+
+   #[test]
+   fn has_enough_molecules_scenarios() {
+      let table = vec![
+         (at_molecules().inventory("A2 B1").sample(sample(1, 10, "A2 B1")), true, "exact inventory"),
+         (at_molecules().inventory("A2").sample(sample(1, 10, "A2 B1")), false, "missing one molecule"),
+         (at_molecules().expertise("B4").inventory("A2").sample(sample(1, 10, "A2 B1")), true, "expertise exceeds cost"),
+         (
+            at_molecules().inventory("A2").sample(sample(1, 10, "A2")).sample(sample(2, 20, "A2")),
+            false,
+            "molecules are spent by the first sample",
+         ),
+         (
+            at_molecules()
+               .inventory("A3")
+               .sample(SampleBuilder::new(1).health(20).cost("A1").gain('A'))
+               .sample(SampleBuilder::new(2).health(10).cost("A3")),
+            true,
+            "expertise gained by the first sample helps the second",
+         ),
+         (at_molecules(), true, "no samples"),
+      ];
+      for (robot, expected, name) in table {
+         assert_eq!(robot.build().has_enough_molecules(), expected, "{}", name);
+      }
+   }
+
+   #[test]
+   fn can_produce_sample_scenarios() {
+      let table = vec![
+         (at_molecules(), "A2 B1", PLENTY, true, "everything available"),
+         (at_molecules(), "A2 B1", "A2", false, "stock too low"),
+         (at_molecules().expertise("A9 B9"), "A2 B1", "", true, "expertise exceeds cost"),
+         (at_molecules().inventory("A1").expertise("B1"), "A1 B1", "", true, "held and expertise cover cost"),
+         (at_molecules().inventory("C5 D4"), "A2", PLENTY, false, "over the 10 molecule cap"),
+         (at_molecules().inventory("C5 D4"), "A1", PLENTY, true, "exactly at the 10 molecule cap"),
+      ];
+      for (robot, cost, available, expected, name) in table {
+         let robot = robot.build();
+         let sample = sample(1, 10, cost).build();
+         assert_eq!(robot.can_produce_sample(&sample, &molecules(available)), expected, "{}", name);
+      }
+   }
+
+   #[test]
+   fn get_impossible_samples_scenarios() {
+      let robot = at_molecules()
+         .inventory("C5 D3")
+         .expertise("E9")
+         .sample(sample(1, 10, "A1"))
+         .sample(sample(2, 10, "B3"))
+         .sample(sample(3, 10, "E5"))
+         .build();
+      let impossible = robot
+         .get_impossible_samples(&molecules("A1 B5"))
+         .iter()
+         .map(|sample| sample.get_id())
+         .collect::<Vec<_>>();
+      assert_eq!(impossible, vec![2]);
+
+      let impossible = robot
+         .get_impossible_samples(&molecules(""))
+         .iter()
+         .map(|sample| sample.get_id())
+         .collect::<Vec<_>>();
+      assert_eq!(impossible, vec![1, 2]);
+   }
+
+   #[test]
+   fn get_most_interesting_ready_sample_scenarios() {
+      let table = vec![
+         (at_molecules().sample(sample(1, 10, "A1")), None, "nothing held"),
+         (at_molecules().inventory("A1").sample(sample(1, 10, "A1")), Some(1), "single ready sample"),
+         (
+            at_molecules().inventory("A1 B1").sample(sample(1, 10, "A1")).sample(sample(2, 30, "B1")),
+            Some(2),
+            "healthiest ready sample first",
+         ),
+         (
+            at_molecules().inventory("A1").sample(sample(1, 10, "A1")).sample(sample(2, 30, "B1")),
+            Some(1),
+            "healthier sample not ready",
+         ),
+         (at_molecules().expertise("A4").sample(sample(1, 10, "A3")), Some(1), "expertise exceeds cost"),
+      ];
+      for (robot, expected, name) in table {
+         let robot = robot.build();
+         let ready = robot.get_most_interesting_ready_sample().map(|sample| sample.get_id());
+         assert_eq!(ready, expected, "{}", name);
+      }
+   }
+
+   #[test]
+   fn pick_best_molecule_takes_contested_type_first() {
+      let robot = at_molecules().sample(sample(1, 10, "A2 E1")).build();
+      let enemy = at_molecules().sample(sample(2, 10, "E2")).build();
+      let available = molecules("A5 B5 C5 D5 E2");
+      let policy = PickupPolicy::for_robots(&robot, &enemy, &available);
+      let picked = robot.pick_best_molecule(&available, &policy);
+      assert_eq!(picked.map(|molecule| molecule.as_char()), Some('E'));
+   }
+
+   #[test]
+   fn outstanding_needs_and_demand() {
+      let robot = at_molecules()
+         .inventory("A1")
+         .expertise("B1")
+         .sample(sample(1, 10, "A2 B1"))
+         .sample(sample(2, 10, "A1 C2"))
+         .sample(SampleBuilder::new(3))
+         .build();
+      assert_eq!(robot.get_outstanding_needs(), molecules("A2 C2"));
+      assert_eq!(robot.count_samples_needing(), molecules("A2 C1"));
+   }
+
+   #[test]
+   fn speculative_molecule_scenarios() {
+      let policy = PickupPolicy::fixed_order();
+      let table = vec![
+         (at_molecules(), PLENTY, vec![], Some('A'), "rank 1 gaps with no expertise"),
+         (at_molecules().expertise("A1 B1"), PLENTY, vec![], Some('C'), "expertise fills the gap"),
+         (at_molecules().expertise("A1 B1"), "A5 B5 D5", vec![], Some('D'), "only available types"),
+         (at_molecules().inventory("A1 B1 C1 D1 E1"), PLENTY, vec![], None, "spare already covers rank 1"),
+         (at_molecules(), PLENTY, vec![sample(9, 10, "D2")], Some('D'), "planned cloud sample first"),
+         (
+            at_molecules().inventory("C5 D4").sample(sample(1, 10, "A1")),
+            PLENTY,
+            vec![],
+            None,
+            "no capacity left after held needs",
+         ),
+         (
+            at_molecules().inventory("A2").sample(sample(1, 10, "A2 B1")),
+            PLENTY,
+            vec![],
+            Some('A'),
+            "earmarked molecules are not spare",
+         ),
+      ];
+      for (robot, available, planned, expected, name) in table {
+         let robot = robot.build();
+         let planned = planned.into_iter().map(|sample| sample.build()).collect::<Vec<_>>();
+         let planned = planned.iter().collect::<Vec<_>>();
+         let picked = robot.pick_speculative_molecule(&molecules(available), &policy, &planned);
+         assert_eq!(picked.map(|molecule| molecule.as_char()), expected, "{}", name);
+      }
+   }
+
+   #[test]
+   fn pick_cloud_samples_fills_free_slots_with_producible_samples() {
+      let robot = at_molecules().sample(sample(1, 10, "A1")).sample(sample(2, 10, "A1")).build();
+      let cloud = vec![
+         sample(7, 10, "B1").build(),
+         sample(8, 30, "C1").build(),
+         sample(9, 40, "E5").build(),
+         SampleBuilder::new(10).build(),
+      ];
+      let picked = robot
+         .pick_cloud_samples(&cloud, &molecules("A5 B5 C5 D5 E4"))
+         .iter()
+         .map(|sample| sample.get_id())
+         .collect::<Vec<_>>();
+      assert_eq!(picked, vec![8]);
+   }
 }
@@ -1,19 +1,19 @@
 use super::molecules;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SampleRank {
    LotsOfHealth = 3,
    SomeHealth = 2,
    LittleHealth = 1,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SampleHealth {
    Unresearched,
    Researched(u8),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Sample {
    id: u8,
    rank: SampleRank,
@@ -35,6 +35,9 @@ impl Sample {
    pub fn get_id(&self) -> u8 {
       self.id
    }
+   pub fn get_rank(&self) -> &SampleRank {
+      &self.rank
+   }
    pub fn new(
       id: u8,
       rank: SampleRank,
@@ -63,6 +66,8 @@ impl SampleHealth {
 }
 
 impl SampleRank {
+   pub const ALL: [SampleRank; 3] = [SampleRank::LittleHealth, SampleRank::SomeHealth, SampleRank::LotsOfHealth];
+
    pub fn as_value(&self) -> u8 {
       match self {
          SampleRank::LotsOfHealth => 3,
@@ -71,6 +76,15 @@ impl SampleRank {
       }
    }
 
+   // average molecules of one type a freshly drawn sample of this rank costs, rounded up
+   pub fn expected_cost_per_type(&self) -> i8 {
+      match self {
+         SampleRank::LotsOfHealth => 3,
+         SampleRank::SomeHealth => 2,
+         SampleRank::LittleHealth => 1,
+      }
+   }
+
    pub fn from_integer(value: i8) -> Option<Self> {
       match value {
          3 => Some(SampleRank::LotsOfHealth),
@@ -0,0 +1,135 @@
+/*!
+ * Small DSL for describing game positions in tests, only compiled for them.
+ *
+ * Molecule sets are written as letter/count pairs, e.g. "A2 B1", and "" or "-"
+ * for none, the same notation `Molecules` is displayed with:
+ *
+ * ```text
+ * RobotBuilder::at(Module::Molecule)
+ *    .inventory("A2 B1")
+ *    .expertise("C1")
+ *    .sample(SampleBuilder::new(4).health(10).cost("A3 B1").gain('D'))
+ *    .build()
+ * ```
+ */
+
+use super::module;
+use super::molecules;
+use super::robot;
+use super::sample;
+
+pub fn molecules(notation: &str) -> molecules::Molecules {
+   let mut result = molecules::Molecules::new();
+   for token in notation.split_whitespace().filter(|token| *token != "-") {
+      let mut chars = token.chars();
+      let molecule = molecules::Molecule::from_char(chars.next().unwrap()).unwrap();
+      result[molecule] += if chars.as_str().is_empty() {
+         1
+      } else {
+         parse_input!(chars.as_str(), i8)
+      };
+   }
+   result
+}
+
+pub struct SampleBuilder {
+   id: u8,
+   rank: sample::SampleRank,
+   health: sample::SampleHealth,
+   cost: molecules::Molecules,
+   expertise_gain: molecules::Molecules,
+}
+
+impl SampleBuilder {
+   pub fn new(id: u8) -> Self {
+      Self {
+         id,
+         rank: sample::SampleRank::LittleHealth,
+         health: sample::SampleHealth::Unresearched,
+         cost: molecules::Molecules::new(),
+         expertise_gain: molecules::Molecules::new(),
+      }
+   }
+
+   pub fn rank(mut self, rank: i8) -> Self {
+      self.rank = sample::SampleRank::from_integer(rank).unwrap();
+      self
+   }
+
+   pub fn health(mut self, health: u8) -> Self {
+      self.health = sample::SampleHealth::Researched(health);
+      self
+   }
+
+   pub fn cost(mut self, notation: &str) -> Self {
+      self.cost = molecules(notation);
+      self
+   }
+
+   pub fn gain(mut self, letter: char) -> Self {
+      self.expertise_gain = molecules::Molecules::from_letter(letter);
+      self
+   }
+
+   pub fn build(self) -> sample::Sample {
+      sample::Sample::new(self.id, self.rank, self.health, self.cost, self.expertise_gain)
+   }
+}
+
+pub struct RobotBuilder {
+   location: module::Module,
+   eta: u8,
+   score: i16,
+   inventory: molecules::Molecules,
+   expertise: molecules::Molecules,
+   held_samples: Vec<sample::Sample>,
+}
+
+impl RobotBuilder {
+   pub fn at(location: module::Module) -> Self {
+      Self {
+         location,
+         eta: 0,
+         score: 0,
+         inventory: molecules::Molecules::new(),
+         expertise: molecules::Molecules::new(),
+         held_samples: Vec::new(),
+      }
+   }
+
+   pub fn eta(mut self, eta: u8) -> Self {
+      self.eta = eta;
+      self
+   }
+
+   pub fn score(mut self, score: i16) -> Self {
+      self.score = score;
+      self
+   }
+
+   pub fn inventory(mut self, notation: &str) -> Self {
+      self.inventory = molecules(notation);
+      self
+   }
+
+   pub fn expertise(mut self, notation: &str) -> Self {
+      self.expertise = molecules(notation);
+      self
+   }
+
+   pub fn sample(mut self, sample: SampleBuilder) -> Self {
+      self.held_samples.push(sample.build());
+      self
+   }
+
+   pub fn build(self) -> robot::Robot {
+      robot::Robot::from_parts(
+         self.location,
+         self.eta,
+         self.score,
+         self.inventory,
+         self.expertise,
+         self.held_samples,
+      )
+   }
+}
@@ -0,0 +1,207 @@
+/*!
+ * Whole games between two strategies inside one process, each side going
+ * through its own `Memory` exactly like the live bot does.
+ */
+
+use super::command;
+use super::game;
+use super::memory;
+use super::notation;
+use super::random;
+use super::strategy;
+use std::fmt;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub struct Simulation {
+   game: game::Game,
+   players: [memory::Memory; game::PLAYERS],
+}
+
+impl Simulation {
+   /**
+    * Everything random comes from `seed`: the game takes the first split of
+    * its generator, which is the game `Game::new(seed)` makes, and each
+    * strategy the next one.
+    */
+   pub fn new(seed: u64, mut first: Box<dyn strategy::Strategy>, mut second: Box<dyn strategy::Strategy>) -> Self {
+      let mut random = random::Random::new(seed);
+      let game = game::Game::with_random(random.split());
+      first.set_random(random.split());
+      second.set_random(random.split());
+      let mut players = [memory::Memory::with_strategy(first), memory::Memory::with_strategy(second)];
+      for player in players.iter_mut() {
+         player.set_projects(game.get_projects().clone());
+      }
+      Self { game, players }
+   }
+
+   pub fn get_game(&self) -> &game::Game {
+      &self.game
+   }
+   pub fn get_player(&self, player: usize) -> &memory::Memory {
+      &self.players[player]
+   }
+   pub fn get_player_mut(&mut self, player: usize) -> &mut memory::Memory {
+      &mut self.players[player]
+   }
+
+   // one turn for both sides, returns the commands as sent
+   pub fn step(&mut self) -> [command::Output; game::PLAYERS] {
+      let mut outputs = [command::Command::Wait.into(), command::Command::Wait.into()];
+      for (player, memory) in self.players.iter_mut().enumerate() {
+         memory.observe(self.game.state_for(player), Instant::now());
+         let output = memory.process_turn();
+         outputs[player] = memory.finish_turn(output);
+      }
+      self.game.play([Some(outputs[0].get_action().clone()), Some(outputs[1].get_action().clone())]);
+      outputs
+   }
+
+   pub fn run(&mut self) -> [i16; game::PLAYERS] {
+      while !self.game.is_over() {
+         self.step();
+      }
+      self.game.get_scores()
+   }
+}
+
+/**
+ * The first turn on which two versions of player 0, facing the same
+ * opponent on the same seed, sent different commands.
+ */
+#[derive(Debug)]
+pub struct Divergence {
+   position: notation::Position,
+   first: command::Output,
+   second: command::Output,
+}
+
+impl Divergence {
+   pub fn get_position(&self) -> &notation::Position {
+      &self.position
+   }
+   pub fn get_first(&self) -> &command::Output {
+      &self.first
+   }
+   pub fn get_second(&self) -> &command::Output {
+      &self.second
+   }
+}
+
+#[derive(Debug)]
+pub struct DivergenceReport {
+   divergence: Option<Divergence>,
+   first_scores: [i16; game::PLAYERS],
+   second_scores: [i16; game::PLAYERS],
+}
+
+impl DivergenceReport {
+   pub fn get_divergence(&self) -> Option<&Divergence> {
+      self.divergence.as_ref()
+   }
+   pub fn get_first_scores(&self) -> [i16; game::PLAYERS] {
+      self.first_scores
+   }
+   pub fn get_second_scores(&self) -> [i16; game::PLAYERS] {
+      self.second_scores
+   }
+}
+
+impl fmt::Display for DivergenceReport {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match &self.divergence {
+         Some(divergence) => {
+            let state = divergence.position.get_state();
+            writeln!(f, "first divergence on turn {}", state.get_turn())?;
+            writeln!(f, "  first:  {}", divergence.first)?;
+            writeln!(f, "  second: {}", divergence.second)?;
+            writeln!(f, "  position: {}", divergence.position)?;
+         }
+         None => writeln!(f, "no divergence, both versions played the same game")?,
+      }
+      let (first, second) = (self.first_scores, self.second_scores);
+      writeln!(f, "first scored {} against {}", first[0], first[1])?;
+      writeln!(f, "second scored {} against {}", second[0], second[1])?;
+      writeln!(f, "difference {:+}", (second[0] - second[1]) - (first[0] - first[1]))
+   }
+}
+
+/**
+ * Plays `first` and `second` as player 0 against their own copy of the
+ * opponent on the game given by `seed`, in lockstep until their commands
+ * differ, then both games to the end. Messages are not compared.
+ */
+pub fn first_divergence(
+   seed: u64,
+   first: Box<dyn strategy::Strategy>,
+   second: Box<dyn strategy::Strategy>,
+   opponent: &dyn Fn() -> Box<dyn strategy::Strategy>,
+) -> DivergenceReport {
+   let mut first_game = Simulation::new(seed, first, opponent());
+   let mut second_game = Simulation::new(seed, second, opponent());
+   let mut divergence = None;
+   while divergence.is_none() && !first_game.get_game().is_over() {
+      let [first_command, _] = first_game.step();
+      let [second_command, _] = second_game.step();
+      if first_command.get_action() != second_command.get_action() {
+         let player = first_game.get_player(0);
+         divergence = Some(Divergence {
+            position: notation::Position::new(player.get_state().clone(), player.get_tables().get_projects().clone()),
+            first: first_command,
+            second: second_command,
+         });
+      }
+   }
+   DivergenceReport {
+      divergence,
+      first_scores: first_game.run(),
+      second_scores: second_game.run(),
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::game::Game;
+   use super::super::strategy::by_name;
+   use super::{first_divergence, Simulation};
+
+   #[test]
+   fn plays_a_whole_game_deterministically() {
+      let play = |seed: u64| {
+         let mut simulation = Simulation::new(seed, by_name("goal_machine").unwrap(), by_name("greedy_rank_one").unwrap());
+         let scores = simulation.run();
+         assert_eq!(simulation.get_game().get_turn(), Game::MAX_TURNS);
+         assert_eq!(simulation.get_player(0).get_history().get_states().len(), Game::MAX_TURNS as usize);
+         scores
+      };
+      let scores = play(11);
+      assert_eq!(play(11), scores);
+      assert!(scores[0] > 0);
+   }
+
+   #[test]
+   fn strategies_draw_from_the_game_seed() {
+      let play = |seed: u64| {
+         let mut simulation = Simulation::new(seed, by_name("random_legal").unwrap(), by_name("random_legal").unwrap());
+         assert_eq!(simulation.get_game().get_projects(), Game::new(seed).get_projects());
+         (0..40).map(|_| simulation.step().map(|output| output.to_string())).collect::<Vec<_>>()
+      };
+      let commands = play(3);
+      assert_eq!(play(3), commands);
+      assert_ne!(play(4), commands);
+      assert!(commands.iter().any(|[first, second]| first != second));
+   }
+
+   #[test]
+   fn finds_the_first_divergence() {
+      let opponent = || by_name("greedy_rank_one").unwrap();
+      let report = first_divergence(5, by_name("goal_machine").unwrap(), by_name("greedy_rank_one").unwrap(), &opponent);
+      let divergence = report.get_divergence().unwrap();
+      // same sample, but the goal machine picks the most at-risk type and the other one goes A to E
+      assert_eq!(divergence.get_position().get_state().get_turn(), 14);
+      assert_eq!(divergence.get_first().get_action().to_string(), "CONNECT D");
+      assert_eq!(divergence.get_second().get_action().to_string(), "CONNECT A");
+      assert!(report.to_string().contains("first divergence on turn 14\n"));
+   }
+}
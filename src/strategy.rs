@@ -0,0 +1,116 @@
+use super::command;
+use super::goal_machine;
+use super::history;
+use super::opponents;
+use super::random;
+use super::tables;
+use super::time_budget;
+use std::fmt;
+
+/**
+ * Everything a strategy may look at when choosing the command of a turn.
+ */
+pub struct TurnContext<'a> {
+   state: &'a history::TurnState,
+   history: &'a history::History,
+   tables: &'a tables::LookupTables,
+   time_budget: &'a time_budget::TimeBudget,
+}
+
+impl<'a> TurnContext<'a> {
+   pub fn new(
+      state: &'a history::TurnState,
+      history: &'a history::History,
+      tables: &'a tables::LookupTables,
+      time_budget: &'a time_budget::TimeBudget,
+   ) -> Self {
+      Self {
+         state,
+         history,
+         tables,
+         time_budget,
+      }
+   }
+   pub fn get_state(&self) -> &'a history::TurnState {
+      self.state
+   }
+   pub fn get_history(&self) -> &'a history::History {
+      self.history
+   }
+   pub fn get_tables(&self) -> &'a tables::LookupTables {
+      self.tables
+   }
+   pub fn get_time_budget(&self) -> &'a time_budget::TimeBudget {
+      self.time_budget
+   }
+}
+
+pub trait Strategy: fmt::Debug {
+   fn name(&self) -> &'static str;
+   fn decide(&mut self, context: &TurnContext) -> command::Output;
+
+   // what the strategy is working towards, for logs and exports
+   fn get_goal(&self) -> Option<&'static str> {
+      None
+   }
+
+   // resumes from a goal `get_goal` returned, e.g. for a recorded position
+   fn set_goal(&mut self, _goal: &str) -> Result<(), &'static str> {
+      Err("This strategy has no goals")
+   }
+
+   // why the last decision fell back to WAIT, if it had to
+   fn get_error(&self) -> Option<&'static str> {
+      None
+   }
+
+   // replaces whatever the strategy draws from with a split of the game's generator
+   fn set_random(&mut self, _random: random::Random) {}
+}
+
+pub const DEFAULT_STRATEGY: &str = goal_machine::GoalMachineStrategy::NAME;
+// command line argument wins over the variable, the arena passes neither
+pub const STRATEGY_VARIABLE: &str = "CODE4LIFE_STRATEGY";
+
+pub fn names() -> Vec<&'static str> {
+   vec![
+      goal_machine::GoalMachineStrategy::NAME,
+      goal_machine::GoalMachineStrategy::SPECULATIVE_NAME,
+      opponents::BaselineStrategy::NAME,
+      opponents::RandomLegalStrategy::NAME,
+      opponents::GreedyRankOneStrategy::NAME,
+      opponents::MoleculeBlockerStrategy::NAME,
+      opponents::CloudSniperStrategy::NAME,
+   ]
+}
+
+pub fn by_name(name: &str) -> Option<Box<dyn Strategy>> {
+   match name {
+      goal_machine::GoalMachineStrategy::NAME => Some(Box::new(goal_machine::GoalMachineStrategy::new())),
+      goal_machine::GoalMachineStrategy::SPECULATIVE_NAME => {
+         Some(Box::new(goal_machine::GoalMachineStrategy::new().with_speculative_gathering()))
+      }
+      opponents::BaselineStrategy::NAME => Some(Box::new(opponents::BaselineStrategy::new())),
+      opponents::RandomLegalStrategy::NAME => {
+         Some(Box::new(opponents::RandomLegalStrategy::new(opponents::RandomLegalStrategy::DEFAULT_SEED)))
+      }
+      opponents::GreedyRankOneStrategy::NAME => Some(Box::new(opponents::GreedyRankOneStrategy::new())),
+      opponents::MoleculeBlockerStrategy::NAME => Some(Box::new(opponents::MoleculeBlockerStrategy::new())),
+      opponents::CloudSniperStrategy::NAME => Some(Box::new(opponents::CloudSniperStrategy::new())),
+      _ => None,
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::{by_name, names, DEFAULT_STRATEGY};
+
+   #[test]
+   fn every_listed_name_builds_its_strategy() {
+      assert!(names().contains(&DEFAULT_STRATEGY));
+      for name in names() {
+         assert_eq!(by_name(name).map(|strategy| strategy.name()), Some(name));
+      }
+      assert!(by_name("no_such_bot").is_none());
+   }
+}
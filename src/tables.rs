@@ -0,0 +1,58 @@
+use super::module;
+use super::molecules;
+
+/**
+ * What is known from the initial input, read during the long first turn:
+ * the projects and the travel distances between modules.
+ */
+#[derive(Clone, Debug)]
+pub struct LookupTables {
+   distances: [[u8; 5]; 5],
+   projects: Vec<molecules::Molecules>,
+}
+
+impl LookupTables {
+   pub fn new(projects: Vec<molecules::Molecules>) -> Self {
+      let mut distances = [[0; 5]; 5];
+      for from in module::Module::ALL.iter() {
+         for to in module::Module::ALL.iter() {
+            distances[from.index()][to.index()] = Self::travel_distance(from, to);
+         }
+      }
+      Self { distances, projects }
+   }
+
+   fn travel_distance(from: &module::Module, to: &module::Module) -> u8 {
+      use module::Module::*;
+      match (from, to) {
+         _ if from == to => 0,
+         (Spawn, _) | (_, Spawn) => 2,
+         (Diagnosis, Laboratory) | (Laboratory, Diagnosis) => 4,
+         _ => 3,
+      }
+   }
+
+   pub fn distance(&self, from: &module::Module, to: &module::Module) -> u8 {
+      self.distances[from.index()][to.index()]
+   }
+
+   pub fn get_projects(&self) -> &Vec<molecules::Molecules> {
+      &self.projects
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::module::Module;
+   use super::LookupTables;
+
+   #[test]
+   fn distances_follow_the_game_rules() {
+      let tables = LookupTables::new(Vec::new());
+      assert_eq!(tables.distance(&Module::Spawn, &Module::Laboratory), 2);
+      assert_eq!(tables.distance(&Module::Diagnosis, &Module::Laboratory), 4);
+      assert_eq!(tables.distance(&Module::Laboratory, &Module::Diagnosis), 4);
+      assert_eq!(tables.distance(&Module::Sample, &Module::Molecule), 3);
+      assert_eq!(tables.distance(&Module::Molecule, &Module::Molecule), 0);
+   }
+}
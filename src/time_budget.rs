@@ -0,0 +1,177 @@
+use super::command;
+use std::time::{Duration, Instant};
+
+/**
+ * Per-turn time allowance of the arena, measured from the moment the first
+ * input line of the turn is read, or of the initial input on the first turn.
+ * Search routines poll it and stop early enough to still print their best
+ * command.
+ */
+#[derive(Clone, Debug)]
+pub struct TimeBudget {
+   started: Instant,
+   limit: Duration,
+   safety_margin: Duration,
+}
+
+impl TimeBudget {
+   pub const FIRST_TURN_LIMIT: Duration = Duration::from_millis(1000);
+   pub const TURN_LIMIT: Duration = Duration::from_millis(50);
+   pub const DEFAULT_SAFETY_MARGIN: Duration = Duration::from_millis(10);
+
+   pub fn new(started: Instant, limit: Duration) -> Self {
+      Self {
+         started,
+         limit,
+         safety_margin: Self::DEFAULT_SAFETY_MARGIN,
+      }
+   }
+
+   pub fn for_turn(started: Instant, turn: u16) -> Self {
+      if turn == 0 {
+         Self::new(started, Self::FIRST_TURN_LIMIT)
+      } else {
+         Self::new(started, Self::TURN_LIMIT)
+      }
+   }
+
+   pub fn with_safety_margin(mut self, safety_margin: Duration) -> Self {
+      self.safety_margin = safety_margin;
+      self
+   }
+
+   pub fn get_limit(&self) -> Duration {
+      self.limit
+   }
+
+   pub fn get_safety_margin(&self) -> Duration {
+      self.safety_margin
+   }
+
+   pub fn elapsed(&self) -> Duration {
+      self.started.elapsed()
+   }
+
+   // time left for thinking, the safety margin is already subtracted
+   pub fn remaining(&self) -> Duration {
+      self.remaining_at(Instant::now())
+   }
+
+   // the same as `remaining`, as seen at `now`
+   pub fn remaining_at(&self, now: Instant) -> Duration {
+      self.limit
+         .saturating_sub(self.safety_margin)
+         .saturating_sub(now.saturating_duration_since(self.started))
+   }
+
+   pub fn is_exhausted(&self) -> bool {
+      self.remaining().is_zero()
+   }
+
+   /**
+    * Anytime driver: keeps calling `improve` with an increasing iteration
+    * number while time is left. `improve` returns a better command, or None
+    * once it has nothing more to search. The best command found so far is
+    * returned, `fallback` if no iteration finished.
+    */
+   pub fn run_anytime<F>(&self, fallback: command::Command, improve: F) -> command::Command
+   where
+      F: FnMut(usize) -> Option<command::Command>,
+   {
+      self.run_anytime_with_clock(fallback, Instant::now, improve)
+   }
+
+   // `run_anytime` reading the time from `clock`, so tests do not depend on the machine's load
+   pub fn run_anytime_with_clock<C, F>(&self, fallback: command::Command, mut clock: C, mut improve: F) -> command::Command
+   where
+      C: FnMut() -> Instant,
+      F: FnMut(usize) -> Option<command::Command>,
+   {
+      let mut best = fallback;
+      let mut iteration = 0;
+      while !self.remaining_at(clock()).is_zero() {
+         match improve(iteration) {
+            Some(command) => best = command,
+            None => break,
+         }
+         iteration += 1;
+      }
+      best
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::module::Module;
+   use super::TimeBudget;
+   use std::cell::Cell;
+   use std::time::{Duration, Instant};
+
+   // a clock that moves 1ms each time it is read
+   fn ticking(started: Instant) -> impl FnMut() -> Instant {
+      let ticks = Cell::new(0);
+      move || {
+         let now = started + Duration::from_millis(ticks.get());
+         ticks.set(ticks.get() + 1);
+         now
+      }
+   }
+
+   #[test]
+   fn first_turn_gets_the_long_limit() {
+      let now = Instant::now();
+      assert_eq!(TimeBudget::for_turn(now, 0).get_limit(), TimeBudget::FIRST_TURN_LIMIT);
+      assert_eq!(TimeBudget::for_turn(now, 1).get_limit(), TimeBudget::TURN_LIMIT);
+   }
+
+   #[test]
+   fn remaining_excludes_safety_margin() {
+      let started = Instant::now();
+      let budget = TimeBudget::new(started, Duration::from_millis(50)).with_safety_margin(Duration::from_millis(20));
+      assert_eq!(budget.remaining_at(started), Duration::from_millis(30));
+      assert_eq!(budget.remaining_at(started + Duration::from_millis(25)), Duration::from_millis(5));
+      assert!(budget.remaining_at(started + Duration::from_millis(45)).is_zero());
+      assert!(budget.remaining() <= Duration::from_millis(30));
+   }
+
+   #[test]
+   fn anytime_search_stops_at_the_margin() {
+      let started = Instant::now();
+      let budget = TimeBudget::new(started, Duration::from_millis(40)).with_safety_margin(Duration::from_millis(15));
+      let mut iterations = 0;
+      let command = budget.run_anytime_with_clock(Command::Wait, ticking(started), |iteration| {
+         iterations = iteration + 1;
+         Some(Command::Goto(Module::Laboratory))
+      });
+      assert!(matches!(command, Command::Goto(Module::Laboratory)));
+      // one iteration per millisecond until 40 - 15
+      assert_eq!(iterations, 25);
+   }
+
+   #[test]
+   fn anytime_search_on_the_real_clock_stays_inside_the_margin() {
+      let budget = TimeBudget::new(Instant::now(), TimeBudget::TURN_LIMIT);
+      let command = budget.run_anytime(Command::Wait, |_| {
+         // about the cost of one search iteration
+         std::thread::sleep(Duration::from_millis(1));
+         Some(Command::Goto(Module::Laboratory))
+      });
+      assert!(matches!(command, Command::Goto(Module::Laboratory)));
+      let overshoot = budget.elapsed().saturating_sub(budget.get_limit() - budget.get_safety_margin());
+      assert!(overshoot < TimeBudget::DEFAULT_SAFETY_MARGIN, "overshot by {:?}", overshoot);
+   }
+
+   #[test]
+   fn anytime_search_keeps_fallback_when_nothing_found() {
+      let started = Instant::now();
+      let budget = TimeBudget::new(started, Duration::from_millis(50));
+      let mut calls = 0;
+      let command = budget.run_anytime_with_clock(Command::Wait, ticking(started), |_| {
+         calls += 1;
+         None
+      });
+      assert!(matches!(command, Command::Wait));
+      assert_eq!(calls, 1);
+   }
+}
@@ -0,0 +1,136 @@
+/*!
+ * A fixed size table from position hashes to search results, shared across
+ * the branches of a search and kept from one turn to the next.
+ *
+ * Each hash has a single slot, chosen by its low bits. A newcomer replaces
+ * the entry already there when it is the same position, when that entry
+ * dates from an earlier generation (call `new_generation` once per turn),
+ * or when it was searched no deeper than the newcomer. Deep results of the
+ * current turn are therefore only pushed out by results at least as deep.
+ */
+
+#[derive(Clone, Debug)]
+pub struct Entry<T> {
+   hash: u64,
+   depth: u8,
+   generation: u8,
+   value: T,
+}
+
+impl<T> Entry<T> {
+   pub fn get_hash(&self) -> u64 {
+      self.hash
+   }
+   pub fn get_depth(&self) -> u8 {
+      self.depth
+   }
+   pub fn get_value(&self) -> &T {
+      &self.value
+   }
+}
+
+#[derive(Debug)]
+pub struct TranspositionTable<T> {
+   slots: Vec<Option<Entry<T>>>,
+   generation: u8,
+   len: usize,
+}
+
+impl<T> TranspositionTable<T> {
+   // `capacity` is rounded up to a power of two
+   pub fn new(capacity: usize) -> Self {
+      let capacity = capacity.max(1).next_power_of_two();
+      Self {
+         slots: (0..capacity).map(|_| None).collect(),
+         generation: 0,
+         len: 0,
+      }
+   }
+
+   pub fn capacity(&self) -> usize {
+      self.slots.len()
+   }
+   pub fn len(&self) -> usize {
+      self.len
+   }
+   pub fn is_empty(&self) -> bool {
+      self.len == 0
+   }
+
+   fn slot(&self, hash: u64) -> usize {
+      (hash & (self.slots.len() as u64 - 1)) as usize
+   }
+
+   pub fn get(&self, hash: u64) -> Option<&Entry<T>> {
+      self.slots[self.slot(hash)].as_ref().filter(|entry| entry.hash == hash)
+   }
+
+   // true when the value was stored, false when the entry in place was kept
+   pub fn insert(&mut self, hash: u64, depth: u8, value: T) -> bool {
+      let generation = self.generation;
+      let slot = self.slot(hash);
+      let replace = match &self.slots[slot] {
+         None => {
+            self.len += 1;
+            true
+         }
+         Some(entry) => entry.hash == hash || entry.generation != generation || entry.depth <= depth,
+      };
+      if replace {
+         self.slots[slot] = Some(Entry {
+            hash,
+            depth,
+            generation,
+            value,
+         });
+      }
+      replace
+   }
+
+   // entries stored so far can still be read, but give way to any newcomer
+   pub fn new_generation(&mut self) {
+      self.generation = self.generation.wrapping_add(1);
+   }
+
+   pub fn clear(&mut self) {
+      self.slots.iter_mut().for_each(|slot| *slot = None);
+      self.len = 0;
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::TranspositionTable;
+
+   #[test]
+   fn stores_and_finds_by_hash() {
+      let mut table = TranspositionTable::new(5);
+      assert_eq!(table.capacity(), 8);
+      assert!(table.is_empty());
+      assert!(table.insert(0x13, 2, "first"));
+      assert_eq!(table.get(0x13).map(|entry| *entry.get_value()), Some("first"));
+      // same slot, other position
+      assert!(table.get(0x23).is_none());
+      assert!(table.insert(0x13, 1, "again"));
+      assert_eq!(table.get(0x13).map(|entry| entry.get_depth()), Some(1));
+      assert_eq!(table.len(), 1);
+      table.clear();
+      assert!(table.get(0x13).is_none() && table.is_empty());
+   }
+
+   #[test]
+   fn prefers_deep_and_recent_entries() {
+      let mut table = TranspositionTable::new(8);
+      table.insert(0x13, 4, "deep");
+      assert!(!table.insert(0x23, 3, "shallow"));
+      assert_eq!(table.get(0x13).map(|entry| *entry.get_value()), Some("deep"));
+      assert!(table.insert(0x23, 4, "as deep"));
+      assert!(table.get(0x13).is_none());
+
+      table.new_generation();
+      assert_eq!(table.get(0x23).map(|entry| *entry.get_value()), Some("as deep"));
+      assert!(table.insert(0x33, 0, "newer"));
+      assert_eq!(table.get(0x33).map(|entry| entry.get_hash()), Some(0x33));
+      assert_eq!(table.len(), 1);
+   }
+}
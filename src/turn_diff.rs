@@ -0,0 +1,196 @@
+use super::carried_by;
+use super::command;
+use super::connect_options;
+use super::history;
+
+/**
+ * What two consecutive turns tell us about the commands both robots sent.
+ * The protocol never says what the enemy did, so it is rebuilt from the
+ * change in its state; our own command is checked against its effect.
+ */
+#[derive(Debug)]
+pub struct TurnDiff {
+   enemy_command: command::Command,
+   my_command: command::Command,
+   my_command_failed: bool,
+}
+
+impl TurnDiff {
+   pub fn new(
+      previous: &history::TurnState,
+      current: &history::TurnState,
+      issued: Option<&command::Command>,
+   ) -> Self {
+      let my_command = infer_command(previous, current, carried_by::CarriedBy::Me);
+      let my_command_failed = match issued {
+         Some(issued) => !had_effect(previous, issued, &my_command),
+         None => false,
+      };
+      Self {
+         enemy_command: infer_command(previous, current, carried_by::CarriedBy::Other),
+         my_command,
+         my_command_failed,
+      }
+   }
+   pub fn get_enemy_command(&self) -> &command::Command {
+      &self.enemy_command
+   }
+   pub fn get_my_command(&self) -> &command::Command {
+      &self.my_command
+   }
+   pub fn my_command_failed(&self) -> bool {
+      self.my_command_failed
+   }
+}
+
+// most likely command `owner` sent between the two turns, WAIT when nothing changed
+pub fn infer_command(
+   previous: &history::TurnState,
+   current: &history::TurnState,
+   owner: carried_by::CarriedBy,
+) -> command::Command {
+   let (before, after) = match (previous.get_robot(owner), current.get_robot(owner)) {
+      (Some(before), Some(after)) => (before, after),
+      _ => return command::Command::Wait,
+   };
+   if before.get_location() != after.get_location() {
+      return command::Command::Goto(*after.get_location());
+   }
+   if after.get_eta() > 0 || before.get_eta() > 0 {
+      return command::Command::Wait;
+   }
+   for (molecule, count) in (after.get_inventory() - before.get_inventory()).iter() {
+      if count > 0 {
+         return command::Command::Connect(connect_options::ConnectOptions::MoleculeType(molecule));
+      }
+   }
+   for (id, event) in history::History::sample_events_between(previous, current) {
+      let connected = match event {
+         history::SampleEvent::Drawn(by) if by == owner => current
+            .find_sample(id)
+            .map(|(_, sample)| connect_options::ConnectOptions::SampleRank(*sample.get_rank())),
+         history::SampleEvent::Diagnosed(by)
+         | history::SampleEvent::Dropped(by)
+         | history::SampleEvent::TakenFromCloud(by)
+         | history::SampleEvent::Produced(by)
+            if by == owner =>
+         {
+            Some(connect_options::ConnectOptions::SampleId(id))
+         }
+         _ => None,
+      };
+      if let Some(options) = connected {
+         return command::Command::Connect(options);
+      }
+   }
+   command::Command::Wait
+}
+
+fn had_effect(
+   previous: &history::TurnState,
+   issued: &command::Command,
+   inferred: &command::Command,
+) -> bool {
+   let before = previous.get_my_robot();
+   // commands sent while travelling are ignored by the referee
+   if before.get_eta() > 0 {
+      return true;
+   }
+   // a GOTO to the module we stand at is illegal and changes nothing, so it
+   // is inferred as WAIT and counts as failed like in `command::Command::check`
+   issued == inferred
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::command::Command;
+   use super::super::connect_options::ConnectOptions;
+   use super::super::history::TurnState;
+   use super::super::module::Module;
+   use super::super::molecules::Molecule;
+   use super::super::sample::SampleRank;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::TurnDiff;
+
+   fn state(turn: u16, me: RobotBuilder, enemy: RobotBuilder, cloud: Vec<SampleBuilder>) -> TurnState {
+      TurnState::new(
+         turn,
+         me.build(),
+         enemy.build(),
+         cloud.into_iter().map(|sample| sample.build()).collect(),
+         molecules("A5 B5 C5 D5 E5"),
+      )
+   }
+
+   #[test]
+   fn infers_enemy_commands() {
+      let table = vec![
+         (
+            RobotBuilder::at(Module::Sample),
+            RobotBuilder::at(Module::Laboratory).eta(2),
+            vec![],
+            Command::Goto(Module::Laboratory),
+         ),
+         (
+            RobotBuilder::at(Module::Molecule),
+            RobotBuilder::at(Module::Molecule).inventory("C1"),
+            vec![],
+            Command::Connect(ConnectOptions::MoleculeType(Molecule::C)),
+         ),
+         (
+            RobotBuilder::at(Module::Sample),
+            RobotBuilder::at(Module::Sample).sample(SampleBuilder::new(4).rank(2)),
+            vec![],
+            Command::Connect(ConnectOptions::SampleRank(SampleRank::SomeHealth)),
+         ),
+         (
+            RobotBuilder::at(Module::Laboratory).sample(SampleBuilder::new(3).health(10)),
+            RobotBuilder::at(Module::Laboratory),
+            vec![],
+            Command::Connect(ConnectOptions::SampleId(3)),
+         ),
+         (
+            RobotBuilder::at(Module::Diagnosis).sample(SampleBuilder::new(3).health(10)),
+            RobotBuilder::at(Module::Diagnosis),
+            vec![SampleBuilder::new(3).health(10)],
+            Command::Connect(ConnectOptions::SampleId(3)),
+         ),
+         (
+            RobotBuilder::at(Module::Sample),
+            RobotBuilder::at(Module::Sample),
+            vec![],
+            Command::Wait,
+         ),
+      ];
+      for (enemy_before, enemy_after, cloud_after, expected) in table {
+         let previous = state(0, RobotBuilder::at(Module::Spawn), enemy_before, vec![]);
+         let current = state(1, RobotBuilder::at(Module::Spawn), enemy_after, cloud_after);
+         let diff = TurnDiff::new(&previous, &current, None);
+         assert_eq!(diff.get_enemy_command(), &expected);
+         assert!(!diff.my_command_failed());
+      }
+   }
+
+   #[test]
+   fn flags_our_failed_command() {
+      let previous = state(0, RobotBuilder::at(Module::Molecule), RobotBuilder::at(Module::Spawn), vec![]);
+      let taken = state(1, RobotBuilder::at(Module::Molecule).inventory("A1"), RobotBuilder::at(Module::Spawn), vec![]);
+      let unchanged = state(1, RobotBuilder::at(Module::Molecule), RobotBuilder::at(Module::Spawn), vec![]);
+      let connect_a = Command::Connect(ConnectOptions::MoleculeType(Molecule::A));
+
+      assert!(!TurnDiff::new(&previous, &taken, Some(&connect_a)).my_command_failed());
+      assert!(TurnDiff::new(&previous, &unchanged, Some(&connect_a)).my_command_failed());
+      assert!(!TurnDiff::new(&previous, &unchanged, Some(&Command::Wait)).my_command_failed());
+      assert!(TurnDiff::new(&previous, &unchanged, Some(&Command::Goto(Module::Molecule))).my_command_failed());
+      assert!(TurnDiff::new(&previous, &unchanged, Some(&Command::Goto(Module::Sample))).my_command_failed());
+   }
+
+   #[test]
+   fn commands_while_travelling_never_fail() {
+      let previous = state(0, RobotBuilder::at(Module::Molecule).eta(2), RobotBuilder::at(Module::Spawn), vec![]);
+      let current = state(1, RobotBuilder::at(Module::Molecule).eta(1), RobotBuilder::at(Module::Spawn), vec![]);
+      let diff = TurnDiff::new(&previous, &current, Some(&Command::Goto(Module::Sample)));
+      assert_eq!(diff.get_my_command(), &Command::Wait);
+      assert!(!diff.my_command_failed());
+   }
+}
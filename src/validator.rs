@@ -0,0 +1,252 @@
+use super::carried_by;
+use super::history;
+use super::molecules;
+use super::robot;
+use std::collections::HashSet;
+use std::fmt;
+
+/**
+ * Game invariants a freshly parsed turn has to respect, on its own and
+ * compared to the turn before. A violation almost always means a parser bug.
+ */
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+   NegativeCount(&'static str, molecules::Molecule),
+   TooManyMolecules(carried_by::CarriedBy, i16),
+   TooManySamples(carried_by::CarriedBy, usize),
+   DuplicateSampleId(u8),
+   ExpertiseDecreased(carried_by::CarriedBy, molecules::Molecule),
+   ScoreDecreased(carried_by::CarriedBy, i16, i16),
+   MoleculesNotConserved(molecules::Molecule, i16, i16),
+}
+
+impl fmt::Display for Violation {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self {
+         Violation::NegativeCount(what, molecule) => write!(f, "negative {} of {}", what, molecule),
+         Violation::TooManyMolecules(owner, count) => write!(f, "{:?} carries {} molecules", owner, count),
+         Violation::TooManySamples(owner, count) => write!(f, "{:?} carries {} samples", owner, count),
+         Violation::DuplicateSampleId(id) => write!(f, "sample {} appears twice", id),
+         Violation::ExpertiseDecreased(owner, molecule) => write!(f, "{:?} lost expertise in {}", owner, molecule),
+         Violation::ScoreDecreased(owner, before, after) => {
+            write!(f, "{:?} score went from {} to {}", owner, before, after)
+         }
+         Violation::MoleculesNotConserved(molecule, expected, found) => {
+            write!(f, "expected {} {} molecules in play, found {}", expected, molecule, found)
+         }
+      }
+   }
+}
+
+const MAX_MOLECULES: i16 = 10;
+const MAX_SAMPLES: usize = 3;
+
+fn robots(state: &history::TurnState) -> [(carried_by::CarriedBy, &robot::Robot); 2] {
+   [
+      (carried_by::CarriedBy::Me, state.get_my_robot()),
+      (carried_by::CarriedBy::Other, state.get_enemy_robot()),
+   ]
+}
+
+// molecules on the board: in stock and in both inventories
+fn molecules_in_play(state: &history::TurnState) -> molecules::Molecules {
+   &(state.get_available() + state.get_my_robot().get_inventory()) + state.get_enemy_robot().get_inventory()
+}
+
+pub fn validate_state(state: &history::TurnState) -> Vec<Violation> {
+   let mut violations = Vec::new();
+   for (molecule, count) in state.get_available().iter() {
+      if count < 0 {
+         violations.push(Violation::NegativeCount("available", molecule));
+      }
+   }
+   for (owner, robot) in robots(state) {
+      for (molecule, count) in robot.get_inventory().iter() {
+         if count < 0 {
+            violations.push(Violation::NegativeCount("inventory", molecule));
+         }
+      }
+      for (molecule, count) in robot.get_expertise().iter() {
+         if count < 0 {
+            violations.push(Violation::NegativeCount("expertise", molecule));
+         }
+      }
+      if robot.get_inventory().len() > MAX_MOLECULES {
+         violations.push(Violation::TooManyMolecules(owner, robot.get_inventory().len()));
+      }
+      if robot.get_held_samples().len() > MAX_SAMPLES {
+         violations.push(Violation::TooManySamples(owner, robot.get_held_samples().len()));
+      }
+   }
+   let mut seen = HashSet::new();
+   for sample in state.all_samples() {
+      if !seen.insert(sample.get_id()) {
+         violations.push(Violation::DuplicateSampleId(sample.get_id()));
+      }
+   }
+   violations
+}
+
+pub fn validate_transition(previous: &history::TurnState, current: &history::TurnState) -> Vec<Violation> {
+   let mut violations = Vec::new();
+   for ((owner, before), (_, after)) in robots(previous).into_iter().zip(robots(current)) {
+      for (molecule, count) in after.get_expertise().iter() {
+         if count < before.get_expertise()[molecule] {
+            violations.push(Violation::ExpertiseDecreased(owner, molecule));
+         }
+      }
+      if after.get_score() < before.get_score() {
+         violations.push(Violation::ScoreDecreased(owner, before.get_score(), after.get_score()));
+      }
+   }
+
+   // the referee puts spent molecules back in the stock, so producing a
+   // sample moves molecules from an inventory to `available` and the
+   // total per type never changes
+   let expected = molecules_in_play(previous);
+   let found = molecules_in_play(current);
+   for (molecule, count) in found.iter() {
+      if count != expected[molecule] {
+         violations.push(Violation::MoleculesNotConserved(
+            molecule,
+            expected[molecule] as i16,
+            count as i16,
+         ));
+      }
+   }
+   violations
+}
+
+pub fn validate(previous: Option<&history::TurnState>, current: &history::TurnState) -> Vec<Violation> {
+   let mut violations = validate_state(current);
+   if let Some(previous) = previous {
+      violations.extend(validate_transition(previous, current));
+   }
+   violations
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::carried_by::CarriedBy;
+   use super::super::history::TurnState;
+   use super::super::input_reading;
+   use super::super::module::Module;
+   use super::super::molecules::Molecule;
+   use super::super::scenario::{molecules, RobotBuilder, SampleBuilder};
+   use super::{validate, Violation};
+   use std::io::Cursor;
+
+   fn parse_turn(reader: &mut Cursor<&str>, turn: u16) -> TurnState {
+      let (my_robot, enemy_robot, cloud, available, _) = input_reading::parse_turn_input_from(reader);
+      TurnState::new(turn, my_robot, enemy_robot, cloud, available)
+   }
+
+   #[test]
+   fn parsed_game_start_is_consistent() {
+      let input = "3\n\
+         0 3 0 3 3\n\
+         3 3 0 0 3\n\
+         0 0 4 4 0\n\
+         SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+         SAMPLES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+         5 5 5 5 5\n\
+         2\n\
+         0 0 1 A -1 -1 -1 -1 -1 -1\n\
+         1 1 1 B -1 -1 -1 -1 -1 -1\n\
+         MOLECULES 0 0 1 0 0 0 0 0 0 0 0 0\n\
+         MOLECULES 0 0 0 0 0 0 0 0 0 0 0 0\n\
+         4 5 5 5 5\n\
+         2\n\
+         0 0 1 A 10 0 3 0 0 0\n\
+         1 1 1 B 1 0 0 0 2 0\n";
+      let mut reader = Cursor::new(input);
+      let projects = input_reading::parse_projects_from(&mut reader);
+      assert_eq!(projects.len(), 3);
+      assert_eq!(projects[2], molecules("C4 D4"));
+
+      let first = parse_turn(&mut reader, 0);
+      let second = parse_turn(&mut reader, 1);
+      assert_eq!(validate(None, &first), vec![]);
+      assert_eq!(validate(Some(&first), &second), vec![]);
+   }
+
+   #[test]
+   fn catches_broken_invariants() {
+      let previous = TurnState::new(
+         0,
+         RobotBuilder::at(Module::Laboratory)
+            .score(10)
+            .inventory("A2")
+            .expertise("B1")
+            .sample(SampleBuilder::new(1).health(10).cost("A2 B1"))
+            .build(),
+         RobotBuilder::at(Module::Molecule).expertise("C2").build(),
+         Vec::new(),
+         molecules("A3 B5 C5 D5 E5"),
+      );
+      let produced = TurnState::new(
+         1,
+         RobotBuilder::at(Module::Laboratory).score(20).expertise("B1 A1").build(),
+         RobotBuilder::at(Module::Molecule).expertise("C2").build(),
+         Vec::new(),
+         molecules("A5 B5 C5 D5 E5"),
+      );
+      assert_eq!(validate(Some(&previous), &produced), vec![]);
+
+      let broken = TurnState::new(
+         1,
+         RobotBuilder::at(Module::Laboratory)
+            .score(5)
+            .inventory("A2 C9")
+            .sample(SampleBuilder::new(1).health(10))
+            .build(),
+         RobotBuilder::at(Module::Molecule)
+            .expertise("C1")
+            .sample(SampleBuilder::new(1).health(10))
+            .build(),
+         Vec::new(),
+         molecules("A3 B5 C5 D5 E5"),
+      );
+      let violations = validate(Some(&previous), &broken);
+      assert!(violations.contains(&Violation::TooManyMolecules(CarriedBy::Me, 11)));
+      assert!(violations.contains(&Violation::DuplicateSampleId(1)));
+      assert!(violations.contains(&Violation::ExpertiseDecreased(CarriedBy::Me, Molecule::B)));
+      assert!(violations.contains(&Violation::ExpertiseDecreased(CarriedBy::Other, Molecule::C)));
+      assert!(violations.contains(&Violation::ScoreDecreased(CarriedBy::Me, 10, 5)));
+      assert!(violations.contains(&Violation::MoleculesNotConserved(Molecule::C, 5, 14)));
+   }
+
+   #[test]
+   fn spent_molecules_return_to_stock() {
+      let previous = TurnState::new(
+         0,
+         RobotBuilder::at(Module::Laboratory)
+            .inventory("A3 B1")
+            .sample(SampleBuilder::new(1).health(10).cost("A3 B1"))
+            .build(),
+         RobotBuilder::at(Module::Sample).build(),
+         Vec::new(),
+         molecules("A2 B4 C5 D5 E5"),
+      );
+      let produced = TurnState::new(
+         1,
+         RobotBuilder::at(Module::Laboratory).score(10).build(),
+         RobotBuilder::at(Module::Sample).build(),
+         Vec::new(),
+         molecules("A5 B5 C5 D5 E5"),
+      );
+      assert_eq!(validate(Some(&previous), &produced), vec![]);
+
+      // molecules that vanish with the sample are a parser bug
+      let vanished = TurnState::new(
+         1,
+         RobotBuilder::at(Module::Laboratory).score(10).build(),
+         RobotBuilder::at(Module::Sample).build(),
+         Vec::new(),
+         molecules("A2 B4 C5 D5 E5"),
+      );
+      let violations = validate(Some(&previous), &vanished);
+      assert!(violations.contains(&Violation::MoleculesNotConserved(Molecule::A, 5, 2)));
+      assert!(violations.contains(&Violation::MoleculesNotConserved(Molecule::B, 5, 4)));
+   }
+}
@@ -0,0 +1,151 @@
+/*!
+ * Zobrist hashing of positions for transposition tables.
+ *
+ * Every feature of a position (a robot's location and eta, one molecule
+ * count, a held sample...) has its own 64 bit key and a position hashes to
+ * the XOR of the keys of its features. A search that changes one feature
+ * updates the hash with two XORs, removing the old key and adding the new
+ * one, instead of hashing the whole state again.
+ *
+ * Keys are computed from the feature rather than stored in tables, so
+ * scores, expertise and sample ids need no upper bound. The turn is left
+ * out on purpose: the same position on a later turn reuses its evaluation.
+ */
+
+use super::carried_by;
+use super::history;
+use super::module;
+use super::molecules;
+use super::random;
+use super::robot;
+use super::sample;
+
+#[derive(Clone, Copy)]
+enum Feature {
+   Location = 1,
+   Score = 2,
+   Inventory = 3,
+   Expertise = 4,
+   Sample = 5,
+   Available = 6,
+}
+
+// keeps the keys of small features away from small hashes
+const SALT: u64 = 0x2B0B_2157_C0DE_411F;
+
+fn key(owner: carried_by::CarriedBy, feature: Feature, index: usize, value: i64) -> u64 {
+   let owner = match owner {
+      carried_by::CarriedBy::Me => 0,
+      carried_by::CarriedBy::Other => 1,
+      carried_by::CarriedBy::Cloud => 2,
+   };
+   // distinct features give distinct codes, and `mix` is a bijection
+   let code = (owner << 60) | ((feature as u64) << 56) | ((index as u64 & 0xFF_FFFF) << 32) | (value as u32 as u64);
+   random::mix(code ^ SALT)
+}
+
+pub fn location_key(owner: carried_by::CarriedBy, location: &module::Module, eta: u8) -> u64 {
+   key(owner, Feature::Location, location.index(), eta as i64)
+}
+
+pub fn score_key(owner: carried_by::CarriedBy, score: i16) -> u64 {
+   key(owner, Feature::Score, 0, score as i64)
+}
+
+pub fn inventory_key(owner: carried_by::CarriedBy, molecule: molecules::Molecule, count: i8) -> u64 {
+   key(owner, Feature::Inventory, molecule.index(), count as i64)
+}
+
+pub fn expertise_key(owner: carried_by::CarriedBy, molecule: molecules::Molecule, count: i8) -> u64 {
+   key(owner, Feature::Expertise, molecule.index(), count as i64)
+}
+
+pub fn available_key(molecule: molecules::Molecule, count: i8) -> u64 {
+   key(carried_by::CarriedBy::Cloud, Feature::Available, molecule.index(), count as i64)
+}
+
+// a sample is known by its id, diagnosing it changes its key
+pub fn sample_key(owner: carried_by::CarriedBy, sample: &sample::Sample) -> u64 {
+   let diagnosed = match sample.get_health() {
+      sample::SampleHealth::Researched(_) => 1,
+      sample::SampleHealth::Unresearched => 0,
+   };
+   key(owner, Feature::Sample, sample.get_id() as usize, diagnosed)
+}
+
+pub fn hash_robot(owner: carried_by::CarriedBy, robot: &robot::Robot) -> u64 {
+   let mut hash = location_key(owner, robot.get_location(), robot.get_eta()) ^ score_key(owner, robot.get_score());
+   for molecule in molecules::Molecule::ALL {
+      hash ^= inventory_key(owner, molecule, robot.get_inventory()[molecule]);
+      hash ^= expertise_key(owner, molecule, robot.get_expertise()[molecule]);
+   }
+   for sample in robot.get_held_samples() {
+      hash ^= sample_key(owner, sample);
+   }
+   hash
+}
+
+pub fn hash_state(state: &history::TurnState) -> u64 {
+   let mut hash = hash_robot(carried_by::CarriedBy::Me, state.get_my_robot())
+      ^ hash_robot(carried_by::CarriedBy::Other, state.get_enemy_robot());
+   for molecule in molecules::Molecule::ALL {
+      hash ^= available_key(molecule, state.get_available()[molecule]);
+   }
+   for sample in state.get_cloud() {
+      hash ^= sample_key(carried_by::CarriedBy::Cloud, sample);
+   }
+   hash
+}
+
+#[cfg(test)]
+mod tests {
+   use super::super::carried_by::CarriedBy;
+   use super::super::fuzz::PositionGenerator;
+   use super::super::molecules::Molecule;
+   use super::super::notation::Position;
+   use super::{available_key, hash_state, inventory_key, sample_key};
+   use std::collections::HashSet;
+
+   fn hash(position: &str) -> u64 {
+      hash_state(position.parse::<Position>().unwrap().get_state())
+   }
+
+   const POSITION: &str = "12; MOLECULES 0 10 A2B1 C1; SAMPLES 2 0 - -; A3B4C5D5E5; -; 3:m:1:10:A1B2:C 4:c:2:?:?:A";
+
+   #[test]
+   fn same_position_same_hash_whatever_the_turn() {
+      assert_eq!(hash(POSITION), hash(POSITION));
+      assert_eq!(hash(POSITION), hash(&POSITION.replacen("12;", "150;", 1)));
+      let swapped = "12; SAMPLES 2 0 - -; MOLECULES 0 10 A2B1 C1; A3B4C5D5E5; -; 3:e:1:10:A1B2:C 4:c:2:?:?:A";
+      assert_ne!(hash(POSITION), hash(swapped));
+      assert_ne!(hash(POSITION), hash(&POSITION.replace("4:c:2:?:?:A", "4:m:2:?:?:A")));
+      assert_ne!(hash(POSITION), hash(&POSITION.replace("SAMPLES 2", "SAMPLES 1")));
+   }
+
+   #[test]
+   fn updates_incrementally() {
+      // we take an A
+      let taken = POSITION.replace("A2B1", "A3B1").replace("A3B4", "A2B4");
+      let updated = hash(POSITION)
+         ^ inventory_key(CarriedBy::Me, Molecule::A, 2)
+         ^ inventory_key(CarriedBy::Me, Molecule::A, 3)
+         ^ available_key(Molecule::A, 3)
+         ^ available_key(Molecule::A, 2);
+      assert_eq!(updated, hash(&taken));
+
+      // the enemy takes sample 4 from the cloud
+      let position = POSITION.parse::<Position>().unwrap();
+      let sample = &position.get_state().get_cloud()[0];
+      let updated = hash(POSITION) ^ sample_key(CarriedBy::Cloud, sample) ^ sample_key(CarriedBy::Other, sample);
+      assert_eq!(updated, hash(&POSITION.replace("4:c:", "4:e:")));
+   }
+
+   #[test]
+   fn random_positions_do_not_collide() {
+      let mut generator = PositionGenerator::new(50);
+      let mut hashes = HashSet::new();
+      for _ in 0..2000 {
+         assert!(hashes.insert(hash_state(generator.next_position().get_state())));
+      }
+   }
+}
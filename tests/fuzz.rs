@@ -0,0 +1,98 @@
+/*!
+ * Every strategy on random rule-valid positions: no panic, no reported
+ * error, and a legal command that survives printing. `Molecules` clamps to
+ * the `i8` range, so the counts of each position must add and subtract up
+ * without leaving it, or a strategy could compute with clamped garbage.
+ *
+ * `CODE4LIFE_FUZZ_CASES` and `CODE4LIFE_FUZZ_SEED` widen or move the search;
+ * a failure names the seed and the position in notation.
+ */
+
+use code_4_life::command;
+use code_4_life::fuzz;
+use code_4_life::memory;
+use code_4_life::molecules;
+use code_4_life::notation;
+use code_4_life::strategy;
+use std::env;
+use std::panic;
+use std::time::Instant;
+
+const CASES: u64 = 300;
+const SEED: u64 = 0xF022_C0DE;
+
+fn setting(variable: &str, default: u64) -> u64 {
+   env::var(variable).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+fn decide(name: &str, position: &notation::Position) -> Result<command::Output, String> {
+   let mut memory = memory::Memory::with_strategy(strategy::by_name(name).unwrap());
+   memory.load_position(position, Instant::now());
+   let output = memory.process_turn();
+   if let Some(error) = memory.get_strategy().get_error() {
+      return Err(format!("failed: {}", error));
+   }
+   Ok(output)
+}
+
+// every count of the position summed up, and taken away from zero, fits an i8
+fn counts_fit(position: &notation::Position) -> bool {
+   let state = position.get_state();
+   let robots = [state.get_my_robot(), state.get_enemy_robot()];
+   let counts = robots
+      .iter()
+      .flat_map(|robot| [robot.get_inventory(), robot.get_expertise()])
+      .chain(state.all_samples().flat_map(|sample| [sample.get_cost(), sample.get_expertise_gain()]))
+      .chain(position.get_projects().iter())
+      .chain([state.get_available()]);
+   let mut total = Some(molecules::Molecules::new());
+   let mut negated = Some(molecules::Molecules::new());
+   for count in counts {
+      total = total.and_then(|total| total.checked_add(count));
+      negated = negated.and_then(|negated| negated.checked_sub(count));
+   }
+   total.is_some() && negated.is_some()
+}
+
+fn check(name: &str, position: &notation::Position) -> Result<(), String> {
+   let output = match panic::catch_unwind(|| decide(name, position)) {
+      Ok(decided) => decided?,
+      Err(_) => return Err("panicked".to_owned()),
+   };
+   let state = position.get_state();
+   let robot = state.get_my_robot();
+   output
+      .get_action()
+      .check(robot, state.get_available(), state.get_cloud())
+      .map_err(|error| format!("played {}: {}", output, error))?;
+   match command::Output::parse_at(&output.to_string(), robot.get_location()) {
+      Ok(parsed) if parsed == output => Ok(()),
+      _ => Err(format!("played {}, which does not read back", output)),
+   }
+}
+
+#[test]
+fn strategies_survive_random_positions() {
+   let seed = setting("CODE4LIFE_FUZZ_SEED", SEED);
+   let mut generator = fuzz::PositionGenerator::new(seed);
+   let mut failures = Vec::new();
+   for _ in 0..setting("CODE4LIFE_FUZZ_CASES", CASES) {
+      let position = generator.next_position();
+      if !counts_fit(&position) {
+         failures.push(format!("counts leave the i8 range\n   {}", position));
+         continue;
+      }
+      for name in strategy::names() {
+         if let Err(error) = check(name, &position) {
+            failures.push(format!("{} {}\n   {}", name, error, position));
+         }
+      }
+   }
+   assert!(
+      failures.is_empty(),
+      "seed {}, {} failures:\n{}",
+      seed,
+      failures.len(),
+      failures.join("\n")
+   );
+}
@@ -0,0 +1,42 @@
+/*!
+ * Every file in `tests/golden` against the current decisions, see the
+ * `golden` module for the format and `golden::BLESS_VARIABLE` to update.
+ */
+
+use code_4_life::golden;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn golden_positions_are_still_played() {
+   let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+   let mut paths = fs::read_dir(&directory)
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+      .collect::<Vec<_>>();
+   paths.sort();
+   assert!(!paths.is_empty());
+
+   let bless = env::var_os(golden::BLESS_VARIABLE).is_some();
+   let mut failures = Vec::new();
+   for path in paths {
+      let text = fs::read_to_string(&path).unwrap();
+      let mut file = text.parse::<golden::GoldenFile>().unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+      if bless {
+         if file.bless() > 0 {
+            fs::write(&path, file.to_string()).unwrap();
+         }
+         continue;
+      }
+      failures.extend(file.check().into_iter().map(|failure| format!("{}: {}", path.display(), failure)));
+   }
+   assert!(
+      failures.is_empty(),
+      "{} golden positions changed, rerun with {}=1 if intended:\n{}",
+      failures.len(),
+      golden::BLESS_VARIABLE,
+      failures.join("\n")
+   );
+}